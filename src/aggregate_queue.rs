@@ -0,0 +1,260 @@
+#![allow(unused)]
+//! Queues that keep an aggregate of their elements, built from two
+//! aggregating stacks.
+//!
+//! New elements are pushed onto `back`. `pop` takes from `front`, and when
+//! that's empty first moves everything from `back` over, reversing the order.
+//! Each element is moved at most once, so push, pop and the aggregate are all
+//! amortized *O*(1).
+use crate::auxiliary_stack::{AggregateStack, Combined, MinMaxStack, Semigroup};
+
+/// Queue that maintains the aggregate of its elements under a [`Semigroup`].
+pub struct AggregateQueue<T, M: Semigroup<T>> {
+    // Oldest element on top. Combines in reverse so its aggregate is still in
+    // queue order.
+    front: AggregateStack<T, Rev<M>>,
+    back: AggregateStack<T, M>,
+}
+
+impl<T, M> AggregateQueue<T, M>
+where
+    M: Semigroup<T> + Clone + Default,
+{
+    pub fn new() -> Self {
+        Self::with_semigroup(M::default())
+    }
+}
+
+impl<T, M> AggregateQueue<T, M>
+where
+    M: Semigroup<T> + Clone,
+{
+    pub fn with_semigroup(semigroup: M) -> Self {
+        Self {
+            front: AggregateStack::with_semigroup(Rev(semigroup.clone())),
+            back: AggregateStack::with_semigroup(semigroup),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.back.push(value);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            while let Some(value) = self.back.pop() {
+                self.front.push(value);
+            }
+        }
+        self.front.pop()
+    }
+
+    /// Returns the aggregate of all elements, or `None` if the queue is empty.
+    ///
+    /// The two halves' aggregates are combined on every call, so this returns
+    /// an owned value.
+    pub fn aggregate(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let semigroup = self.back.semigroup();
+        match (self.front.aggregate(), self.back.aggregate()) {
+            (Some(front), Some(back)) => Some(match semigroup.combine(front, back) {
+                Combined::Left => front.clone(),
+                Combined::Right => back.clone(),
+                Combined::Value(agg) => agg,
+            }),
+            (Some(agg), None) | (None, Some(agg)) => Some(agg.clone()),
+            (None, None) => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Queue that supports .push(x), .pop(), .min() and .max() in amortized
+/// constant time, without requiring elements to be `Clone`.
+pub struct MinQueue<T> {
+    front: MinMaxStack<T>,
+    back: MinMaxStack<T>,
+}
+
+impl<T: Ord> MinQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            front: MinMaxStack::new(),
+            back: MinMaxStack::new(),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.back.push(value);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            while let Some(value) = self.back.pop() {
+                self.front.push(value);
+            }
+        }
+        self.front.pop()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        match (self.front.min(), self.back.min()) {
+            (Some(front), Some(back)) => Some(front.min(back)),
+            (front, back) => front.or(back),
+        }
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        match (self.front.max(), self.back.max()) {
+            (Some(front), Some(back)) => Some(front.max(back)),
+            (front, back) => front.or(back),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns an iterator over the aggregates of every window of `k` consecutive
+/// elements of `iter`.
+///
+/// # Panics
+///
+/// Panics if `k` is 0.
+pub fn sliding_window<I, M>(iter: I, k: usize, op: M) -> Windows<I::IntoIter, M>
+where
+    I: IntoIterator,
+    M: Semigroup<I::Item> + Clone,
+{
+    assert!(k > 0, "window size must be positive");
+    Windows {
+        iter: iter.into_iter(),
+        k,
+        queue: AggregateQueue::with_semigroup(op),
+    }
+}
+
+/// Iterator returned by [`sliding_window`].
+pub struct Windows<I, M>
+where
+    I: Iterator,
+    M: Semigroup<I::Item>,
+{
+    iter: I,
+    k: usize,
+    queue: AggregateQueue<I::Item, M>,
+}
+
+impl<I, M> Iterator for Windows<I, M>
+where
+    I: Iterator,
+    I::Item: Clone,
+    M: Semigroup<I::Item> + Clone,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.queue.push(self.iter.next()?);
+            if self.queue.len() > self.k {
+                self.queue.pop();
+            }
+            if self.queue.len() == self.k {
+                return self.queue.aggregate();
+            }
+        }
+    }
+}
+
+/// A semigroup with its operands swapped.
+#[derive(Clone)]
+struct Rev<M>(M);
+
+impl<T, M: Semigroup<T>> Semigroup<T> for Rev<M> {
+    fn combine(&self, left: &T, right: &T) -> Combined<T> {
+        match self.0.combine(right, left) {
+            Combined::Left => Combined::Right,
+            Combined::Right => Combined::Left,
+            value => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary_stack::{Min, Sum};
+
+    #[test]
+    fn test_aggregate() {
+        let mut queue: AggregateQueue<u32, Sum> = AggregateQueue::new();
+        assert_eq!(queue.aggregate(), None);
+        for i in 1..=4 {
+            queue.push(i);
+        }
+        assert_eq!(queue.aggregate(), Some(10));
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(10);
+        assert_eq!(queue.aggregate(), Some(19));
+        assert_eq!(queue.len(), 4);
+    }
+
+    #[test]
+    fn test_order() {
+        // Concatenation isn't commutative
+        let concat = |a: &String, b: &String| format!("{}{}", a, b);
+        let mut queue = AggregateQueue::with_semigroup(concat);
+        for s in ["a", "b", "c"] {
+            queue.push(s.to_string());
+        }
+        queue.pop();
+        queue.push("d".to_string());
+        assert_eq!(queue.aggregate().as_deref(), Some("bcd"));
+    }
+
+    #[test]
+    fn test_min_queue() {
+        let mut queue = MinQueue::new();
+        for i in [3, 1, 4, 1, 5] {
+            queue.push(i);
+        }
+        assert_eq!(queue.min(), Some(&1));
+        assert_eq!(queue.max(), Some(&5));
+
+        queue.pop();
+        queue.pop();
+        assert_eq!(queue.min(), Some(&1));
+        queue.pop();
+        queue.pop();
+        assert_eq!(queue.min(), Some(&5));
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.min(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let values = [1, 3, -1, -3, 5, 3, 6, 7];
+        let mins: Vec<i32> = sliding_window(values, 3, Min).collect();
+        assert_eq!(mins, vec![-1, -3, -3, -3, 3, 3]);
+
+        let maxes: Vec<i32> = sliding_window(values, 3, |a: &i32, b: &i32| *a.max(b)).collect();
+        assert_eq!(maxes, vec![3, 3, 5, 5, 6, 7]);
+
+        assert_eq!(sliding_window(values, 9, Min).next(), None);
+    }
+}
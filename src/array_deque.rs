@@ -4,15 +4,29 @@
 //! container. It also has *O*(1) indexing like a vector. The contained elements
 //! are not required to be copyable, and the queue will be sendable if the
 //! contained type is sendable.
+//!
+//! The buffer doubles when full and halves when less than a quarter full, so
+//! memory is released after a burst.
 #![allow(unused)]
 
+use crate::error::{TryPushError, TryReserveError};
+use std::cmp::Ordering;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ops::{Bound, Index, IndexMut, RangeBounds};
+use std::{ptr, slice};
+
 const DEFAULT_CAPACITY: usize = 4;
 
 /// A double-ended queue implemented with a growable ring buffer.
+///
+/// Slots `head..head + len` (wrapping around the end of `buf`) are
+/// initialized, all others are not.
 pub struct VecDeque<T> {
     head: usize,
     len: usize,
-    buf: Vec<Option<T>>,
+    buf: Vec<MaybeUninit<T>>,
+    max_capacity: usize,
 }
 
 impl<T> VecDeque<T> {
@@ -24,20 +38,35 @@ impl<T> VecDeque<T> {
     /// Creates an empty deque with space for at least `capacity` elements.
     pub fn with_capacity(capacity: usize) -> Self {
         let mut buf = Vec::new();
-        buf.resize_with(capacity, Default::default);
+        buf.resize_with(capacity, MaybeUninit::uninit);
         Self {
             head: 0,
             len: 0,
             buf,
+            max_capacity: usize::MAX,
         }
     }
 
+    /// Creates an empty deque that never grows past `max_capacity` elements.
+    /// Adding to a full deque then fails with
+    /// [`TryReserveError::CapacityExceeded`] (the `try_` variants) or panics.
+    pub fn with_max_capacity(max_capacity: usize) -> Self {
+        let mut deque = VecDeque::with_capacity(std::cmp::min(DEFAULT_CAPACITY, max_capacity));
+        deque.max_capacity = max_capacity;
+        deque
+    }
+
     /// Returns the number of elements the deque can hold without
     /// reallocating.    
     pub fn capacity(&self) -> usize {
         self.buf.len()
     }
 
+    /// Returns the number of elements in the deque.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
     /// Returns `true` if the deque is empty.
     pub fn is_empty(&self) -> bool {
         self.len == 0
@@ -45,20 +74,39 @@ impl<T> VecDeque<T> {
 
     /// Appends an element to the back of the deque.
     pub fn push_back(&mut self, value: T) {
+        self.try_push_back(value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`VecDeque::push_back`], but returns an error instead of
+    /// panicking if the deque can't grow. The error gives the value back.
+    pub fn try_push_back(&mut self, value: T) -> Result<(), TryPushError<T>> {
         if self.is_full() {
-            self.grow();
+            if let Err(error) = self.try_grow() {
+                return Err(TryPushError::new(value, error));
+            }
         }
 
-        let next = (self.head + self.len) % self.capacity();
-        self.buf[next] = Some(value);
+        let next = self.to_physical(self.len);
+        self.buf[next].write(value);
 
         self.len += 1;
+        Ok(())
     }
 
     /// Prepends an element to the deque.
     pub fn push_front(&mut self, value: T) {
+        self.try_push_front(value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`VecDeque::push_front`], but returns an error instead of
+    /// panicking if the deque can't grow. The error gives the value back.
+    pub fn try_push_front(&mut self, value: T) -> Result<(), TryPushError<T>> {
         if self.is_full() {
-            self.grow();
+            if let Err(error) = self.try_grow() {
+                return Err(TryPushError::new(value, error));
+            }
         }
 
         self.head = if self.head == 0 {
@@ -67,34 +115,433 @@ impl<T> VecDeque<T> {
             self.head - 1
         };
 
-        self.buf[self.head] = Some(value);
+        self.buf[self.head].write(value);
 
         self.len += 1;
+        Ok(())
     }
 
     /// Removes the last element from the deque and returns it, or `None` if
     /// it is empty.
     pub fn pop_back(&mut self) -> Option<T> {
-        if self.is_empty() {
-            None
-        } else {
-            self.len -= 1;
-            let i = (self.head + self.len) % self.capacity();
-            self.buf[i].take()
-        }
+        let value = self.take_back();
+        self.shrink_if_sparse();
+        value
     }
 
     /// Removes the first element and returns it, or `None` if the deque is
     /// empty.
     pub fn pop_front(&mut self) -> Option<T> {
-        if self.is_empty() {
-            None
+        let value = self.take_front();
+        self.shrink_if_sparse();
+        value
+    }
+
+    /// Provides a reference to the element at the given index, or `None` if
+    /// the index is out of bounds. Element at index 0 is the front.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len {
+            return None;
+        }
+        let i = self.to_physical(i);
+        Some(unsafe { self.buf[i].assume_init_ref() })
+    }
+
+    /// Provides a mutable reference to the element at the given index, or
+    /// `None` if the index is out of bounds.
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        if i >= self.len {
+            return None;
+        }
+        let i = self.to_physical(i);
+        Some(unsafe { self.buf[i].assume_init_mut() })
+    }
+
+    /// Provides a reference to the front element, or `None` if the deque is
+    /// empty.
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Provides a reference to the back element, or `None` if the deque is
+    /// empty.
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    /// Provides a mutable reference to the front element, or `None` if the
+    /// deque is empty.
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    /// Provides a mutable reference to the back element, or `None` if the
+    /// deque is empty.
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.len.checked_sub(1).and_then(|i| self.get_mut(i))
+    }
+
+    /// Inserts an element at index `i`, shifting whichever side of the deque
+    /// is shorter. Runs in *O*(min(i, len - i)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is greater than the deque's length.
+    pub fn insert(&mut self, i: usize, value: T) {
+        self.try_insert(i, value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`VecDeque::insert`], but returns an error instead of panicking
+    /// if the deque can't grow. The error gives the value back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is greater than the deque's length.
+    pub fn try_insert(&mut self, i: usize, value: T) -> Result<(), TryPushError<T>> {
+        assert!(i <= self.len, "index out of bounds");
+        if self.is_full() {
+            if let Err(error) = self.try_grow() {
+                return Err(TryPushError::new(value, error));
+            }
+        }
+
+        if i < self.len - i {
+            // Move the first `i` elements one slot towards the front.
+            self.head = self.wrap_sub(self.head, 1);
+            for k in 0..i {
+                unsafe { self.move_element(k + 1, k) };
+            }
+        } else {
+            // Move the last `len - i` elements one slot towards the back.
+            for k in (i..self.len).rev() {
+                unsafe { self.move_element(k, k + 1) };
+            }
+        }
+
+        let i = self.to_physical(i);
+        self.buf[i].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes and returns the element at index `i`, shifting whichever side
+    /// of the deque is shorter, or returns `None` if `i` is out of bounds.
+    /// Runs in *O*(min(i, len - i)).
+    pub fn remove(&mut self, i: usize) -> Option<T> {
+        if i >= self.len {
+            return None;
+        }
+
+        let value = unsafe { self.buf[self.to_physical(i)].assume_init_read() };
+        if i < self.len - 1 - i {
+            for k in (0..i).rev() {
+                unsafe { self.move_element(k, k + 1) };
+            }
+            self.head = self.wrap_add(self.head, 1);
         } else {
-            let old_head = self.buf[self.head].take();
-            self.head = (self.head + 1) % self.capacity();
-            self.len -= 1;
-            old_head
+            for k in i + 1..self.len {
+                unsafe { self.move_element(k, k - 1) };
+            }
+        }
+        self.len -= 1;
+        self.shrink_if_sparse();
+        Some(value)
+    }
+
+    /// Swaps elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len && j < self.len, "index out of bounds");
+        let (i, j) = (self.to_physical(i), self.to_physical(j));
+        self.buf.swap(i, j);
+    }
+
+    /// Rotates the deque `n` places to the left, so the element at index `n`
+    /// becomes the front. Runs in *O*(min(n, len - n)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the deque's length.
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        if n <= self.len - n {
+            self.move_front_to_back(n);
+        } else {
+            self.move_back_to_front(self.len - n);
+        }
+    }
+
+    /// Rotates the deque `n` places to the right, so the element at index 0
+    /// moves to index `n`. Runs in *O*(min(n, len - n)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the deque's length.
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        if n <= self.len - n {
+            self.move_back_to_front(n);
+        } else {
+            self.move_front_to_back(self.len - n);
+        }
+    }
+
+    /// Returns a pair of slices which contain, in order, the contents of the
+    /// deque.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (front, back) = self.slice_ranges();
+        unsafe {
+            (
+                slice_assume_init(&self.buf[front.0..front.1]),
+                slice_assume_init(&self.buf[back.0..back.1]),
+            )
+        }
+    }
+
+    /// Returns a pair of mutable slices which contain, in order, the contents
+    /// of the deque.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (front, back) = self.slice_ranges();
+        let (rest, front_slice) = self.buf.split_at_mut(front.0);
+        let front_slice = &mut front_slice[..front.1 - front.0];
+        let back_slice = &mut rest[back.0..back.1];
+        unsafe {
+            (
+                slice_assume_init_mut(front_slice),
+                slice_assume_init_mut(back_slice),
+            )
+        }
+    }
+
+    /// Rearranges the internal storage so the elements are contiguous and
+    /// returns them as a single mutable slice.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.head + self.len > self.capacity() {
+            self.buf.rotate_left(self.head);
+            self.head = 0;
+        }
+        self.as_mut_slices().0
+    }
+
+    /// Binary searches this sorted deque for a given element. Returns the
+    /// index of a matching element in `Ok`, or the index where it could be
+    /// inserted keeping the order in `Err`.
+    pub fn binary_search(&self, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.binary_search_by(|e| e.cmp(x))
+    }
+
+    /// Binary searches this sorted deque with a comparator function, see
+    /// [`VecDeque::binary_search`].
+    pub fn binary_search_by<F>(&self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match f(&self[mid]) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(low)
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (front, back) = self.as_slices();
+        Iter {
+            front: front.iter(),
+            back: back.iter(),
+        }
+    }
+
+    /// Returns a front-to-back iterator that returns mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (front, back) = self.as_mut_slices();
+        IterMut {
+            front: front.iter_mut(),
+            back: back.iter_mut(),
+        }
+    }
+
+    /// Returns an iterator over the given range of the deque.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end or the end is
+    /// greater than the deque's length.
+    pub fn range<R>(&self, range: R) -> Iter<'_, T>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = self.to_bounds(range);
+        let (front, back) = self.as_slices();
+        let (front, back) = if end <= front.len() {
+            (&front[start..end], &back[..0])
+        } else if start >= front.len() {
+            (&back[start - front.len()..end - front.len()], &back[..0])
+        } else {
+            (&front[start..], &back[..end - front.len()])
+        };
+        Iter {
+            front: front.iter(),
+            back: back.iter(),
+        }
+    }
+
+    /// Removes the given range from the deque and returns the removed elements
+    /// as an iterator. The range is removed even if the iterator is not
+    /// consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end or the end is
+    /// greater than the deque's length.
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = self.to_bounds(range);
+        let tail_len = self.len - end;
+        // If the `Drain` is leaked, the elements from `start` on are leaked
+        // with it but the deque stays valid.
+        self.len = start;
+        Drain {
+            deque: self,
+            front: start,
+            back: end,
+            end,
+            tail_len,
+        }
+    }
+
+    /// Retains only the elements specified by the predicate, preserving
+    /// their order.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut kept = 0;
+        for i in 0..self.len {
+            if f(&self[i]) {
+                self.swap(kept, i);
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+
+    /// Shortens the deque, keeping the first `len` elements and dropping the
+    /// rest. Does nothing if `len` is not less than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop_back();
+        }
+    }
+
+    /// Removes all elements from the deque.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+        self.head = 0;
+    }
+
+    /// Splits the deque into two at the given index. Returns a new deque
+    /// containing the elements `[at, len)`, leaving `[0, at)` in `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the deque's length.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "index out of bounds");
+        let mut other = VecDeque::with_capacity(self.len - at);
+        for i in at..self.len {
+            let i = self.to_physical(i);
+            other.push_back(unsafe { self.buf[i].assume_init_read() });
+        }
+        self.len = at;
+        self.shrink_if_sparse();
+        other
+    }
+
+    /// Moves all the elements of `other` to the back of `self`, leaving
+    /// `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.reserve(other.len);
+        while let Some(value) = other.pop_front() {
+            self.push_back(value);
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`VecDeque::reserve`], but returns an error instead of panicking
+    /// if the capacity would overflow, exceed `max_capacity` or the allocator
+    /// fails.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required <= self.capacity() {
+            return Ok(());
         }
+        let new_capacity = std::cmp::max(required, self.capacity().saturating_mul(2));
+        self.try_grow_to(std::cmp::min(
+            new_capacity,
+            std::cmp::max(required, self.max_capacity),
+        ))
+    }
+
+    /// Shrinks the capacity of the deque as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the capacity to the larger of `min_capacity` and the current
+    /// length. Does nothing if the capacity is already smaller.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let new_capacity = std::cmp::max(self.len, min_capacity);
+        if new_capacity >= self.capacity() {
+            return;
+        }
+        self.buf.rotate_left(self.head);
+        self.buf.truncate(new_capacity);
+        self.buf.shrink_to_fit();
+        self.head = 0;
+    }
+
+    /// Removes the first element without shrinking the buffer.
+    fn take_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let value = unsafe { self.buf[self.head].assume_init_read() };
+        self.head = self.wrap_add(self.head, 1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Removes the last element without shrinking the buffer.
+    fn take_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        let i = self.to_physical(self.len);
+        Some(unsafe { self.buf[i].assume_init_read() })
     }
 
     /// Returns `true` if the buffer is at full capacity.
@@ -102,23 +549,362 @@ impl<T> VecDeque<T> {
         self.len == self.capacity()
     }
 
-    // Double the buffer size.
-    fn grow(&mut self) {
-        self.buf.rotate_left(self.head);
+    // Double the buffer size, up to `max_capacity`.
+    fn try_grow(&mut self) -> Result<(), TryReserveError> {
+        let new_capacity = std::cmp::max(self.capacity().saturating_mul(2), 1);
+        self.try_grow_to(std::cmp::min(
+            new_capacity,
+            std::cmp::max(self.capacity() + 1, self.max_capacity),
+        ))
+    }
 
-        let new_capacity = std::cmp::max(self.capacity() * 2, 1);
-        let mut new_buf = Vec::new();
-        new_buf.resize_with(new_capacity, Default::default);
+    // Halve the buffer size once less than a quarter of it is used. Together
+    // with doubling on growth this keeps push and pop amortized O(1) while
+    // releasing memory after a burst.
+    fn shrink_if_sparse(&mut self) {
+        if self.capacity() > DEFAULT_CAPACITY && self.len * 4 < self.capacity() {
+            self.shrink_to(std::cmp::max(self.capacity() / 2, DEFAULT_CAPACITY));
+        }
+    }
 
-        let mut old_buf = std::mem::replace(&mut self.buf, new_buf);
-        self.buf.splice(..old_buf.len(), old_buf);
+    fn try_grow_to(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        if new_capacity > self.max_capacity {
+            return Err(TryReserveError::CapacityExceeded {
+                max_capacity: self.max_capacity,
+            });
+        }
+        self.buf.try_reserve_exact(new_capacity - self.capacity())?;
+        self.buf.rotate_left(self.head);
+        self.buf.resize_with(new_capacity, MaybeUninit::uninit);
         self.head = 0;
+        Ok(())
+    }
+
+    /// Resolves range bounds against the deque's length.
+    fn to_bounds<R>(&self, range: R) -> (usize, usize)
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i + 1,
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.len,
+        };
+        assert!(start <= end, "range start is greater than range end");
+        assert!(end <= self.len, "range end out of bounds");
+        (start, end)
+    }
+
+    /// Maps a logical index to a slot in the buffer.
+    fn to_physical(&self, i: usize) -> usize {
+        self.wrap_add(self.head, i)
+    }
+
+    fn wrap_add(&self, index: usize, n: usize) -> usize {
+        (index + n) % self.capacity()
+    }
+
+    fn wrap_sub(&self, index: usize, n: usize) -> usize {
+        (index + self.capacity() - n) % self.capacity()
+    }
+
+    /// Moves the element at logical index `src` to logical index `dst`,
+    /// leaving `src` uninitialized.
+    unsafe fn move_element(&mut self, src: usize, dst: usize) {
+        let src = self.to_physical(src);
+        let dst = self.to_physical(dst);
+        let value = self.buf[src].assume_init_read();
+        self.buf[dst].write(value);
+    }
+
+    fn move_front_to_back(&mut self, n: usize) {
+        for _ in 0..n {
+            unsafe { self.move_element(0, self.len) };
+            self.head = self.wrap_add(self.head, 1);
+        }
+    }
+
+    fn move_back_to_front(&mut self, n: usize) {
+        for _ in 0..n {
+            self.head = self.wrap_sub(self.head, 1);
+            unsafe { self.move_element(self.len, 0) };
+        }
+    }
+
+    /// Returns the `[start, end)` slot ranges of the two initialized halves.
+    fn slice_ranges(&self) -> ((usize, usize), (usize, usize)) {
+        let tail = self.head + self.len;
+        if tail <= self.capacity() {
+            ((self.head, tail), (0, 0))
+        } else {
+            ((self.head, self.capacity()), (0, tail - self.capacity()))
+        }
+    }
+}
+
+unsafe fn slice_assume_init<T>(slice: &[MaybeUninit<T>]) -> &[T] {
+    &*(slice as *const [MaybeUninit<T>] as *const [T])
+}
+
+unsafe fn slice_assume_init_mut<T>(slice: &mut [MaybeUninit<T>]) -> &mut [T] {
+    &mut *(slice as *mut [MaybeUninit<T>] as *mut [T])
+}
+
+impl<T> Drop for VecDeque<T> {
+    fn drop(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        unsafe {
+            ptr::drop_in_place(front);
+            ptr::drop_in_place(back);
+        }
+    }
+}
+
+impl<T> Index<usize> for VecDeque<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("Out of bounds access")
+    }
+}
+
+impl<T> IndexMut<usize> for VecDeque<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        self.get_mut(i).expect("Out of bounds access")
+    }
+}
+
+impl<T> Default for VecDeque<T> {
+    fn default() -> Self {
+        VecDeque::new()
+    }
+}
+
+impl<T: Clone> Clone for VecDeque<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for VecDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for VecDeque<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for VecDeque<T> {}
+
+impl<T> Extend<T> for VecDeque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for VecDeque<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> FromIterator<T> for VecDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = VecDeque::with_capacity(0);
+        deque.extend(iter);
+        deque
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: slice::Iter<'a, T>,
+    back: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    front: slice::IterMut<'a, T>,
+    back: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+/// Owning iterator. The buffer is freed all at once when it's dropped rather
+/// than shrunk along the way.
+pub struct IntoIter<T>(VecDeque<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.take_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.take_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+/// Iterator returned by [`VecDeque::drain`].
+///
+/// Elements are read straight out of the ring. Dropping it drops whatever
+/// wasn't yielded and closes the gap by moving the shorter of the two
+/// remaining parts.
+pub struct Drain<'a, T> {
+    deque: &'a mut VecDeque<T>,
+    // Logical indices of the next elements to yield from either end.
+    front: usize,
+    back: usize,
+    // End of the drained range and number of elements after it.
+    end: usize,
+    tail_len: usize,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let i = self.deque.to_physical(self.front);
+        self.front += 1;
+        Some(unsafe { self.deque.buf[i].assume_init_read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Drain<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        let i = self.deque.to_physical(self.back);
+        Some(unsafe { self.deque.buf[i].assume_init_read() })
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+
+        let deque = &mut *self.deque;
+        // `deque.len` was set to the start of the range.
+        let start = deque.len;
+        let gap = self.end - start;
+        if start < self.tail_len {
+            for k in (0..start).rev() {
+                unsafe { deque.move_element(k, k + gap) };
+            }
+            deque.head = deque.wrap_add(deque.head, gap);
+        } else {
+            for k in 0..self.tail_len {
+                unsafe { deque.move_element(self.end + k, start + k) };
+            }
+        }
+        deque.len = start + self.tail_len;
+        deque.shrink_if_sparse();
+    }
+}
+
+impl<T> IntoIterator for VecDeque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a VecDeque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut VecDeque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::VecDeque;
+    use crate::error::TryReserveError;
 
     #[test]
     fn basic() {
@@ -155,10 +941,269 @@ mod test {
         deque.push_back(3);
         deque.push_back(4);
         assert_eq!(deque.capacity(), 6);
-        assert_eq!(deque.buf[0], Some(1));
-        assert_eq!(deque.buf[1], Some(2));
-        assert_eq!(deque.buf[2], Some(3));
-        assert_eq!(deque.buf[3], Some(4));
-        assert_eq!(deque.buf[4], None);
+        assert_eq!(deque.head, 0);
+        assert_eq!(deque.as_slices(), (&[1, 2, 3, 4][..], &[][..]));
+    }
+
+    fn from_slice(values: &[i32]) -> VecDeque<i32> {
+        let mut deque = VecDeque::with_capacity(values.len());
+        for &v in values {
+            deque.push_back(v);
+        }
+        deque
+    }
+
+    fn to_vec(deque: &VecDeque<i32>) -> Vec<i32> {
+        let (front, back) = deque.as_slices();
+        [front, back].concat()
+    }
+
+    #[test]
+    fn get() {
+        let mut deque = VecDeque::with_capacity(4);
+        assert_eq!(deque.front(), None);
+        assert_eq!(deque.back(), None);
+
+        deque.push_back(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        deque.push_front(0);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(deque.get(0), Some(&0));
+        assert_eq!(deque.get(3), Some(&3));
+        assert_eq!(deque.get(4), None);
+        assert_eq!(deque.front(), Some(&0));
+        assert_eq!(deque.back(), Some(&3));
+        assert_eq!(deque.as_slices(), (&[0, 1][..], &[2, 3][..]));
+
+        deque[1] = 10;
+        *deque.back_mut().unwrap() = 30;
+        assert_eq!(to_vec(&deque), vec![0, 10, 2, 30]);
+
+        deque.swap(0, 3);
+        assert_eq!(to_vec(&deque), vec![30, 10, 2, 0]);
+    }
+
+    #[test]
+    fn insert_remove() {
+        for i in 0..=5 {
+            let mut deque = VecDeque::with_capacity(8);
+            deque.push_back(3);
+            deque.push_back(4);
+            deque.push_back(5);
+            deque.push_front(2); // wraps around
+            deque.push_front(1);
+            deque.insert(i, 100);
+            let mut expected = vec![1, 2, 3, 4, 5];
+            expected.insert(i, 100);
+            assert_eq!(to_vec(&deque), expected);
+            assert_eq!(deque.remove(i), Some(100));
+            assert_eq!(to_vec(&deque), vec![1, 2, 3, 4, 5]);
+        }
+
+        let mut deque = from_slice(&[0, 1, 2, 3, 4]);
+        assert_eq!(deque.remove(5), None);
+        assert_eq!(deque.remove(1), Some(1));
+        assert_eq!(deque.remove(3), Some(4));
+        assert_eq!(to_vec(&deque), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut deque = from_slice(&[0, 1, 2, 3, 4]);
+        deque.pop_back();
+        deque.rotate_left(1);
+        assert_eq!(to_vec(&deque), vec![1, 2, 3, 0]);
+        deque.rotate_left(3);
+        assert_eq!(to_vec(&deque), vec![0, 1, 2, 3]);
+        deque.rotate_right(1);
+        assert_eq!(to_vec(&deque), vec![3, 0, 1, 2]);
+        deque.rotate_right(3);
+        assert_eq!(to_vec(&deque), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn make_contiguous() {
+        let mut deque = from_slice(&[3, 4]);
+        deque.push_front(2);
+        deque.push_front(1);
+        assert_eq!(deque.as_slices(), (&[1, 2][..], &[3, 4][..]));
+        assert_eq!(deque.make_contiguous(), &[1, 2, 3, 4]);
+        assert_eq!(deque.as_slices(), (&[1, 2, 3, 4][..], &[][..]));
+    }
+
+    #[test]
+    fn binary_search() {
+        let mut deque = from_slice(&[5, 7]);
+        deque.push_front(3);
+        deque.push_front(1);
+        assert_eq!(deque.binary_search(&1), Ok(0));
+        assert_eq!(deque.binary_search(&7), Ok(3));
+        assert_eq!(deque.binary_search(&4), Err(2));
+        assert_eq!(deque.binary_search(&8), Err(4));
+        assert_eq!(deque.binary_search(&0), Err(0));
+    }
+
+    #[test]
+    fn iter() {
+        let mut deque: VecDeque<i32> = (2..5).collect();
+        deque.push_front(1);
+        deque.push_front(0);
+
+        let mut iter = deque.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&1, &2, &3]);
+
+        for value in &mut deque {
+            *value *= 10;
+        }
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            vec![40, 30, 20, 10, 0]
+        );
+
+        let mut iter = deque.into_iter();
+        assert_eq!(iter.next_back(), Some(40));
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![0, 10, 20, 30]);
+    }
+
+    #[test]
+    fn range_and_drain() {
+        let mut deque: VecDeque<i32> = (3..8).collect();
+        deque.extend(&[8, 9]);
+        deque.push_front(2);
+        deque.push_front(1);
+        deque.push_front(0);
+
+        assert_eq!(
+            deque.range(2..5).copied().collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert_eq!(deque.range(..2).copied().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(deque.range(8..).copied().collect::<Vec<_>>(), vec![8, 9]);
+        assert_eq!(deque.range(4..=4).copied().collect::<Vec<_>>(), vec![4]);
+
+        let drained: Vec<_> = deque.drain(2..6).collect();
+        assert_eq!(drained, vec![2, 3, 4, 5]);
+        assert_eq!(deque, (0..2).chain(6..10).collect());
+
+        deque.drain(..1);
+        assert_eq!(deque, vec![1, 6, 7, 8, 9].into_iter().collect());
+        deque.drain(..);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn drain_in_place() {
+        use std::rc::Rc;
+
+        // Wrapped around the end of the buffer
+        let mut deque = VecDeque::with_capacity(8);
+        for i in 0..6 {
+            deque.push_back(i);
+        }
+        for i in 0..4 {
+            deque.pop_front();
+            deque.push_back(6 + i);
+        }
+        assert_eq!(deque.as_slices().1.len(), 2);
+
+        // Moves the front part to close the gap
+        let mut drain = deque.drain(1..3);
+        assert_eq!(drain.next_back(), Some(6));
+        drop(drain);
+        assert_eq!(to_vec(&deque), vec![4, 7, 8, 9]);
+        // Moves the back part
+        assert_eq!(deque.drain(2..3).len(), 1);
+        assert_eq!(to_vec(&deque), vec![4, 7, 9]);
+        assert_eq!(deque.capacity(), 8);
+
+        // Unconsumed elements are dropped exactly once
+        let value = Rc::new(());
+        let mut deque: VecDeque<_> = (0..10).map(|_| value.clone()).collect();
+        let mut drain = deque.drain(2..8);
+        drain.next();
+        drain.next_back();
+        drop(drain);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(Rc::strong_count(&value), 5);
+        drop(deque);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn retain_truncate_split() {
+        let mut deque: VecDeque<i32> = (0..10).collect();
+        deque.retain(|v| v % 3 != 0);
+        assert_eq!(deque, vec![1, 2, 4, 5, 7, 8].into_iter().collect());
+
+        deque.truncate(4);
+        assert_eq!(deque, vec![1, 2, 4, 5].into_iter().collect());
+
+        let mut other = deque.split_off(1);
+        assert_eq!(deque, vec![1].into_iter().collect());
+        assert_eq!(other, vec![2, 4, 5].into_iter().collect());
+
+        other.push_front(0);
+        deque.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(format!("{:?}", deque), "[1, 0, 2, 4, 5]");
+        assert_eq!(deque.clone(), deque);
+    }
+
+    #[test]
+    fn shrink() {
+        let mut deque: VecDeque<i32> = (0..64).collect();
+        assert_eq!(deque.capacity(), 64);
+
+        while deque.len() > 16 {
+            deque.pop_front();
+        }
+        assert_eq!(deque.capacity(), 64);
+        deque.pop_back();
+        assert_eq!(deque.capacity(), 32);
+        assert_eq!(deque.front(), Some(&48));
+        assert_eq!(deque.back(), Some(&62));
+
+        deque.shrink_to(20);
+        assert_eq!(deque.capacity(), 20);
+        deque.push_front(47);
+        deque.shrink_to_fit();
+        assert_eq!(deque.capacity(), 16);
+        assert_eq!(deque, (47..63).collect());
+
+        deque.clear();
+        assert_eq!(deque.capacity(), super::DEFAULT_CAPACITY);
+    }
+
+    #[test]
+    fn max_capacity() {
+        let mut deque = VecDeque::with_max_capacity(5);
+        for i in 0..4 {
+            assert_eq!(deque.try_push_back(i), Ok(()));
+        }
+        assert_eq!(deque.try_push_front(-1), Ok(()));
+        assert_eq!(deque.capacity(), 5);
+
+        let err = TryReserveError::CapacityExceeded { max_capacity: 5 };
+        let rejected = deque.try_push_back(4).unwrap_err();
+        assert_eq!(rejected.into_parts(), (4, err.clone()));
+        let rejected = deque.try_push_front(-2).unwrap_err();
+        assert_eq!(rejected.into_parts(), (-2, err.clone()));
+        let rejected = deque.try_insert(2, 100).unwrap_err();
+        assert_eq!(rejected.into_parts(), (100, err.clone()));
+        let err = Err(err);
+        assert_eq!(deque, (-1..4).collect());
+
+        deque.pop_front();
+        assert_eq!(deque.try_reserve(1), Ok(()));
+        assert_eq!(deque.try_reserve(2), err);
+        assert_eq!(
+            deque.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
     }
 }
@@ -1,11 +1,18 @@
 #![allow(unused)]
 
+use crate::error::{TryPushError, TryReserveError};
+use std::cmp;
+use std::mem::MaybeUninit;
+
 const DEFAULT_CAPACITY: usize = 4;
 
+/// A FIFO queue backed by a ring buffer that doubles when full and halves
+/// when less than a quarter full, giving amortized *O*(1) push and pop.
 pub struct Queue<T> {
-    buf: Vec<Option<T>>,
+    buf: Vec<MaybeUninit<T>>,
     start: usize,
     len: usize,
+    max_capacity: usize,
 }
 
 impl<T> Queue<T> {
@@ -15,51 +22,137 @@ impl<T> Queue<T> {
 
     pub fn with_capacity(capacity: usize) -> Self {
         let mut buf = Vec::new();
-        buf.resize_with(capacity, Default::default);
+        buf.resize_with(capacity, MaybeUninit::uninit);
         Self {
             buf,
             start: 0,
             len: 0,
+            max_capacity: usize::MAX,
         }
     }
 
+    /// Creates an empty queue that never grows past `max_capacity` elements.
+    /// Pushing into a full queue then fails with
+    /// [`TryReserveError::CapacityExceeded`] (`try_push`) or panics (`push`).
+    pub fn with_max_capacity(max_capacity: usize) -> Self {
+        let mut queue = Queue::with_capacity(cmp::min(DEFAULT_CAPACITY, max_capacity));
+        queue.max_capacity = max_capacity;
+        queue
+    }
+
     pub fn push(&mut self, value: T) {
+        self.try_push(value).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`Queue::push`], but returns an error instead of panicking if the
+    /// queue can't grow. The error gives the value back.
+    pub fn try_push(&mut self, value: T) -> Result<(), TryPushError<T>> {
         if self.len >= self.capacity() {
-            self.resize()
+            if let Err(error) = self.try_resize() {
+                return Err(TryPushError::new(value, error));
+            }
         }
         let next = (self.start + self.len) % self.capacity();
-        self.buf[next] = Some(value);
+        self.buf[next].write(value);
         self.len += 1;
+        Ok(())
+    }
+
+    /// Reserves capacity for at least `additional` more elements, without
+    /// exceeding `max_capacity`.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required <= self.capacity() {
+            return Ok(());
+        }
+        let new_capacity = cmp::max(required, self.capacity().saturating_mul(2));
+        self.try_grow_to(cmp::min(
+            new_capacity,
+            cmp::max(required, self.max_capacity),
+        ))
     }
 
     pub fn pop(&mut self) -> Option<T> {
         if self.len == 0 {
             return None;
         }
-        let result = self.buf[self.start].take();
+        let result = unsafe { self.buf[self.start].assume_init_read() };
         self.len -= 1;
         self.start = (self.start + 1) % self.capacity();
-        result
+        if self.capacity() > DEFAULT_CAPACITY && self.len * 4 < self.capacity() {
+            self.shrink_to(cmp::max(self.capacity() / 2, DEFAULT_CAPACITY));
+        }
+        Some(result)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    fn resize(&mut self) {
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Shrinks the capacity as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the capacity to the larger of `min_capacity` and the current
+    /// length. Does nothing if the capacity is already smaller.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let new_capacity = cmp::max(self.len, min_capacity);
+        if new_capacity >= self.capacity() {
+            return;
+        }
         self.buf.rotate_left(self.start);
-        let new_capacity = std::cmp::max(self.capacity() * 2, 1);
-        let mut buf = Vec::new();
-        buf.resize_with(new_capacity, Default::default);
-        let mut old = std::mem::replace(&mut self.buf, buf);
-        self.buf.splice(..old.len(), old);
+        self.buf.truncate(new_capacity);
+        self.buf.shrink_to_fit();
         self.start = 0;
     }
 
-    fn capacity(&self) -> usize {
-        self.buf.len()
+    fn try_resize(&mut self) -> Result<(), TryReserveError> {
+        let new_capacity = cmp::max(self.capacity().saturating_mul(2), 1);
+        self.try_grow_to(cmp::min(
+            new_capacity,
+            cmp::max(self.capacity() + 1, self.max_capacity),
+        ))
+    }
+
+    fn try_grow_to(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        if new_capacity > self.max_capacity {
+            return Err(TryReserveError::CapacityExceeded {
+                max_capacity: self.max_capacity,
+            });
+        }
+        self.buf.try_reserve_exact(new_capacity - self.capacity())?;
+        self.buf.rotate_left(self.start);
+        self.buf.resize_with(new_capacity, MaybeUninit::uninit);
+        self.start = 0;
+        Ok(())
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        for i in 0..self.len {
+            let i = (self.start + i) % self.capacity();
+            unsafe { self.buf[i].assume_init_drop() };
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Queue;
+    use super::{Queue, DEFAULT_CAPACITY};
+    use crate::error::TryReserveError;
 
     #[test]
     fn basic() {
@@ -84,4 +177,77 @@ mod test {
         assert_eq!(queue.pop(), Some(7));
         assert_eq!(queue.pop(), None);
     }
+
+    #[test]
+    fn shrink() {
+        let mut queue = Queue::new();
+        for i in 0..64 {
+            queue.push(i);
+        }
+        assert_eq!(queue.capacity(), 64);
+
+        for i in 0..48 {
+            assert_eq!(queue.pop(), Some(i));
+        }
+        assert_eq!(queue.len(), 16);
+        assert_eq!(queue.capacity(), 64);
+
+        // Dropping below a quarter halves the buffer
+        assert_eq!(queue.pop(), Some(48));
+        assert_eq!(queue.capacity(), 32);
+
+        for i in 49..60 {
+            assert_eq!(queue.pop(), Some(i));
+        }
+        assert_eq!(queue.capacity(), 16);
+
+        queue.shrink_to(6);
+        assert_eq!(queue.capacity(), 6);
+        queue.shrink_to_fit();
+        assert_eq!(queue.capacity(), 4);
+        for i in 60..64 {
+            assert_eq!(queue.pop(), Some(i));
+        }
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.capacity(), DEFAULT_CAPACITY);
+    }
+
+    #[test]
+    fn drop_remaining() {
+        let value = std::rc::Rc::new(());
+        let mut queue = Queue::with_capacity(2);
+        for _ in 0..5 {
+            queue.push(value.clone());
+        }
+        queue.pop();
+        drop(queue);
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn max_capacity() {
+        let mut queue = Queue::with_max_capacity(6);
+        for i in 0..6 {
+            assert_eq!(queue.try_push(i), Ok(()));
+        }
+        assert_eq!(queue.capacity(), 6);
+        let rejected = queue.try_push(6).unwrap_err();
+        assert_eq!(
+            rejected.error(),
+            &TryReserveError::CapacityExceeded { max_capacity: 6 }
+        );
+        assert_eq!(rejected.into_value(), 6);
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.try_push(6), Ok(()));
+
+        let mut queue: Queue<u8> = Queue::with_max_capacity(10);
+        assert_eq!(queue.try_reserve(8), Ok(()));
+        assert_eq!(queue.capacity(), 8);
+        assert!(queue.try_reserve(11).is_err());
+        queue.push(0);
+        assert_eq!(
+            queue.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+    }
 }
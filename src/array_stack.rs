@@ -1,10 +1,23 @@
 #![allow(unused)]
+//! ArrayStack: a list backed by a single array, as described in
+//! [Open Data Structures](https://opendatastructures.org/ods-java/2_1_ArrayStack_Fast_Stack_O.html).
+//!
+//! `get`/`set` run in *O*(1), `add(i)`/`remove(i)` in *O*(n - i) amortized.
+//! The array doubles when full and shrinks to twice the length once it's
+//! three times larger than needed.
+use crate::error::{TryPushError, TryReserveError};
+use std::cmp;
+use std::mem::MaybeUninit;
+use std::ops::{Index, IndexMut};
+use std::{ptr, slice};
 
 const DEFAULT_CAPACITY: usize = 4;
 
+/// Slots `0..len` are initialized, all others are not.
 pub struct Stack<T> {
-    buf: Vec<Option<T>>,
+    buf: Vec<MaybeUninit<T>>,
     len: usize,
+    max_capacity: usize,
 }
 
 impl<T> Stack<T> {
@@ -14,85 +27,116 @@ impl<T> Stack<T> {
 
     pub fn with_capacity(capacity: usize) -> Self {
         let mut buf = Vec::new();
-        buf.resize_with(capacity, Default::default);
-        Self { buf, len: 0 }
+        buf.resize_with(capacity, MaybeUninit::uninit);
+        Self {
+            buf,
+            len: 0,
+            max_capacity: usize::MAX,
+        }
     }
 
-    pub fn set(&mut self, i: usize, value: T) -> Option<T> {
-        if i >= self.capacity() {
-            self.resize(i);
-        }
+    /// Creates an empty stack that never grows past `max_capacity` elements.
+    /// Adding to a full stack then fails with
+    /// [`TryReserveError::CapacityExceeded`] (the `try_` variants) or panics.
+    pub fn with_max_capacity(max_capacity: usize) -> Self {
+        let mut stack = Stack::with_capacity(cmp::min(DEFAULT_CAPACITY, max_capacity));
+        stack.max_capacity = max_capacity;
+        stack
+    }
 
-        let old = self.buf[i].take();
-        self.buf[i] = Some(value);
-        // We increase len only if we replace None element.
-        if old.is_none() {
-            self.len += 1;
-        }
-        old
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.as_slice().get(i)
     }
 
-    pub fn get(&mut self, i: usize) -> Option<&T> {
-        if i >= self.capacity() {
-            return None;
-        }
-        match &self.buf[i] {
-            Some(v) => Some(&v),
-            None => None,
-        }
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.as_mut_slice().get_mut(i)
     }
 
-    /// Adds an element to stack. Dynamically resizes if necessary,
-    /// shifts existing elements to make room for the new one.
+    /// Replaces the element at index `i` and returns the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) -> T {
+        std::mem::replace(&mut self[i], value)
+    }
+
+    /// Inserts an element at index `i`, shifting all elements after it to the
+    /// right. Dynamically resizes if necessary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is greater than the stack's length.
     pub fn add(&mut self, i: usize, value: T) {
-        if i >= self.capacity() {
-            self.resize(i);
-        }
+        self.try_add(i, value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        match self.buf[i] {
-            Some(_) => {
-                self.shift_right(i);
-                self.buf[i] = Some(value);
+    /// Like [`Stack::add`], but returns an error instead of panicking if the
+    /// backing array can't grow. The error gives the value back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is greater than the stack's length.
+    pub fn try_add(&mut self, i: usize, value: T) -> Result<(), TryPushError<T>> {
+        assert!(i <= self.len, "index out of bounds");
+        if self.len == self.capacity() {
+            if let Err(error) = self.try_grow() {
+                return Err(TryPushError::new(value, error));
             }
-            None => self.buf[i] = Some(value),
         }
+
+        self.buf[i..=self.len].rotate_right(1);
+        self.buf[i].write(value);
         self.len += 1;
+        Ok(())
     }
 
+    /// Removes and returns the element at index `i`, shifting all elements
+    /// after it to the left, or returns `None` if `i` is out of bounds.
     pub fn remove(&mut self, i: usize) -> Option<T> {
-        if i >= self.capacity() {
+        if i >= self.len {
             return None;
         }
+
+        let value = unsafe { self.buf[i].assume_init_read() };
+        self.buf[i..self.len].rotate_left(1);
         self.len -= 1;
-        self.buf[i].take()
+        self.shrink_if_sparse();
+        Some(value)
     }
 
-    fn resize(&mut self, n: usize) {
-        let mut new_capacity = self.capacity();
-        while n >= new_capacity {
-            new_capacity = std::cmp::max(new_capacity * 2, 1);
-        }
+    /// Appends an element to the end of the stack.
+    pub fn push(&mut self, value: T) {
+        self.add(self.len, value)
+    }
 
-        let mut buf = Vec::new();
-        buf.resize_with(new_capacity, Default::default);
-        let mut old = std::mem::replace(&mut self.buf, buf);
-        self.buf.splice(..old.len(), old);
+    /// Like [`Stack::push`], but returns an error instead of panicking if the
+    /// backing array can't grow. The error gives the value back.
+    pub fn try_push(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        self.try_add(self.len, value)
     }
 
-    fn shift_right(&mut self, mut i: usize) {
-        let mut tmp = None;
-        loop {
-            if i >= self.capacity() {
-                self.resize(i);
-            }
+    /// Removes the last element and returns it, or `None` if the stack is
+    /// empty.
+    pub fn pop(&mut self) -> Option<T> {
+        self.len.checked_sub(1).and_then(|i| self.remove(i))
+    }
 
-            std::mem::swap(&mut tmp, &mut self.buf[i]);
-            i += 1;
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { &*(&self.buf[..self.len] as *const [MaybeUninit<T>] as *const [T]) }
+    }
 
-            if let None = tmp {
-                break;
-            }
-        }
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { &mut *(&mut self.buf[..self.len] as *mut [MaybeUninit<T>] as *mut [T]) }
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
     }
 
     pub fn capacity(&self) -> usize {
@@ -102,91 +146,277 @@ impl<T> Stack<T> {
     pub fn len(&self) -> usize {
         self.len
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Doubles the backing array, but never past `max_capacity`.
+    fn try_grow(&mut self) -> Result<(), TryReserveError> {
+        if self.capacity() >= self.max_capacity {
+            return Err(TryReserveError::CapacityExceeded {
+                max_capacity: self.max_capacity,
+            });
+        }
+        let new_capacity = cmp::min(
+            cmp::max(self.capacity().saturating_mul(2), 1),
+            self.max_capacity,
+        );
+        self.buf.try_reserve_exact(new_capacity - self.capacity())?;
+        self.buf.resize_with(new_capacity, MaybeUninit::uninit);
+        Ok(())
+    }
+
+    /// Shrinks the backing array to twice the length once it's at least three
+    /// times the length, which keeps `add` and `remove` amortized.
+    fn shrink_if_sparse(&mut self) {
+        if self.capacity() > DEFAULT_CAPACITY && self.capacity() >= 3 * self.len {
+            let new_capacity = cmp::max(2 * self.len, DEFAULT_CAPACITY);
+            self.buf.truncate(new_capacity);
+            self.buf.shrink_to_fit();
+        }
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+impl<T> Index<usize> for Stack<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("Out of bounds access")
+    }
+}
+
+impl<T> IndexMut<usize> for Stack<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        self.get_mut(i).expect("Out of bounds access")
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stack<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Stack<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T> {
+    stack: Stack<T>,
+    front: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.stack.len {
+            return None;
+        }
+        let value = unsafe { self.stack.buf[self.front].assume_init_read() };
+        self.front += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.stack.len - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.stack.len {
+            return None;
+        }
+        self.stack.len -= 1;
+        Some(unsafe { self.stack.buf[self.stack.len].assume_init_read() })
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> Drop for IntoIter<T> {
+    fn drop(&mut self) {
+        // Drop the elements not yielded yet and leave none for `Stack::drop`.
+        for i in self.front..self.stack.len {
+            unsafe { self.stack.buf[i].assume_init_drop() };
+        }
+        self.stack.len = 0;
+    }
+}
+
+impl<T> IntoIterator for Stack<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            stack: self,
+            front: 0,
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::Stack;
+    use crate::error::TryReserveError;
+    use std::rc::Rc;
+
+    fn to_vec(stack: &Stack<u8>) -> Vec<u8> {
+        stack.iter().copied().collect()
+    }
 
     #[test]
     fn add() {
         let mut stack: Stack<u8> = Stack::new();
         assert_eq!(stack.len(), 0);
 
+        stack.add(0, 2);
+        stack.add(0, 0);
+        stack.add(1, 1);
+        stack.add(3, 3);
+        assert_eq!(to_vec(&stack), vec![0, 1, 2, 3]);
+        assert_eq!(stack.len(), 4);
+        assert_eq!(stack.capacity(), 4);
+
         // Should resize backing array
-        stack.add(8, 13);
-        assert_eq!(stack.get(8), Some(&13));
-        assert_eq!(stack.capacity(), 16);
-        assert_eq!(stack.len(), 1);
+        stack.add(2, 13);
+        assert_eq!(to_vec(&stack), vec![0, 1, 13, 2, 3]);
+        assert_eq!(stack.capacity(), 8);
+    }
 
-        stack.add(100, 13);
-        assert_eq!(stack.get(100), Some(&13));
-        assert_eq!(stack.capacity(), 128);
+    #[test]
+    #[should_panic]
+    fn add_out_of_bounds() {
+        let mut stack: Stack<u8> = Stack::new();
+        stack.add(1, 13);
     }
 
     #[test]
     fn set() {
         let mut stack: Stack<u8> = Stack::new();
 
-        stack.add(0, 13);
+        stack.push(13);
         assert_eq!(stack.len(), 1);
         assert_eq!(stack.get(0), Some(&13));
 
         // Should overwrite
-        stack.set(0, 69);
+        assert_eq!(stack.set(0, 69), 13);
         assert_eq!(stack.get(0), Some(&69));
         assert_eq!(stack.len(), 1);
 
-        // Should resize & set
-        stack.set(100, 13);
-        assert_eq!(stack.get(100), Some(&13));
-        assert_eq!(stack.len(), 2);
+        stack[0] = 42;
+        assert_eq!(stack[0], 42);
+        assert_eq!(stack.get(1), None);
     }
 
     #[test]
     fn remove() {
         let mut stack: Stack<u8> = Stack::new();
+        for i in 0..4 {
+            stack.push(i);
+        }
 
-        stack.add(0, 13);
-        assert_eq!(stack.get(0), Some(&13));
-        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.remove(1), Some(1));
+        assert_eq!(to_vec(&stack), vec![0, 2, 3]);
+        assert_eq!(stack.len(), 3);
 
-        assert_eq!(stack.remove(0), Some(13));
-        assert_eq!(stack.get(0), None);
-        assert_eq!(stack.len(), 0);
+        assert_eq!(stack.remove(2), Some(3));
+        assert_eq!(stack.remove(2), None);
+        assert_eq!(stack.pop(), Some(2));
+        assert_eq!(stack.pop(), Some(0));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
 
-        assert_eq!(stack.remove(100), None);
-        assert_eq!(stack.get(100), None);
+    #[test]
+    fn shrink() {
+        let mut stack: Stack<u8> = Stack::new();
+        for i in 0..32 {
+            stack.push(i);
+        }
+        assert_eq!(stack.capacity(), 32);
+
+        while stack.len() > 11 {
+            stack.pop();
+        }
+        assert_eq!(stack.capacity(), 32);
+
+        // 32 >= 3 * 10, shrink to 2 * 10
+        stack.pop();
+        assert_eq!(stack.capacity(), 20);
+        assert_eq!(to_vec(&stack), (0..10).collect::<Vec<_>>());
     }
 
     #[test]
-    fn shift_right() {
-        let mut stack: Stack<u8> = Stack::with_capacity(4);
+    fn iter() {
+        let mut stack: Stack<u8> = Stack::new();
+        for i in 0..5 {
+            stack.push(i);
+        }
+
+        for value in &mut stack {
+            *value *= 2;
+        }
+        assert_eq!(to_vec(&stack), vec![0, 2, 4, 6, 8]);
+
+        let mut iter = stack.into_iter();
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next_back(), Some(8));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn max_capacity() {
+        let mut stack: Stack<u8> = Stack::with_max_capacity(6);
         assert_eq!(stack.capacity(), 4);
-        stack.set(0, 0);
-        stack.set(1, 1);
-        stack.set(2, 2);
-        stack.set(3, 3);
-        stack.add(0, 13);
-        assert_eq!(stack.get(0), Some(&13));
-        assert_eq!(stack.get(1), Some(&0));
-        assert_eq!(stack.get(2), Some(&1));
-        assert_eq!(stack.get(3), Some(&2));
-        assert_eq!(stack.get(4), Some(&3));
-        assert_eq!(stack.capacity(), 8);
-        assert_eq!(stack.len(), 5);
 
-        let mut stack: Stack<u8> = Stack::new();
-        stack.set(2, 2);
-        stack.add(2, 13);
-        assert_eq!(stack.get(2), Some(&13));
-        assert_eq!(stack.get(3), Some(&2));
+        for i in 0..6 {
+            assert_eq!(stack.try_push(i), Ok(()));
+        }
+        assert_eq!(stack.capacity(), 6);
+        assert_eq!(
+            stack.try_add(0, 13).unwrap_err().into_parts(),
+            (13, TryReserveError::CapacityExceeded { max_capacity: 6 })
+        );
+        assert_eq!(to_vec(&stack), vec![0, 1, 2, 3, 4, 5]);
     }
 
     #[test]
     fn edge_cases() {
-        let mut stack: Stack<u8> = Stack::with_capacity(0);
+        let mut stack: Stack<String> = Stack::with_capacity(0);
         assert_eq!(stack.get(11), None);
-        assert_eq!(stack.set(0, 13), None);
+        stack.push("a".to_string());
+        assert_eq!(stack.capacity(), 1);
+
+        // Partially consumed iterator drops the rest
+        let counter = Rc::new(());
+        let mut stack = Stack::new();
+        for _ in 0..3 {
+            stack.push(counter.clone());
+        }
+        let mut iter = stack.into_iter();
+        assert!(iter.next().is_some());
+        assert_eq!(Rc::strong_count(&counter), 3);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
     }
 }
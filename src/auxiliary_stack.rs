@@ -1,41 +1,259 @@
 #![allow(unused)]
-/// Stack  that supports .push(x), .pop(), and .min(),
-/// which returns the minimum element of S. All operations run in constant time.
-pub struct Stack<T> {
-    stack: Vec<T>,
-    min_stack: Vec<T>,
+//! Stacks that keep an aggregate of all their elements, such as the minimum,
+//! available in constant time.
+//!
+//! Every entry stores the aggregate of itself and everything below it, so
+//! `push` combines one value with the aggregate under it and `pop` just
+//! discards it.
+use std::ops::Add;
+
+/// An associative operation used to aggregate a stack's elements.
+///
+/// There's no identity element, so this is a semigroup rather than a monoid:
+/// an empty stack simply has no aggregate.
+///
+/// Combining can pick one of its operands instead of building a new value.
+/// The stack then records where that operand lives rather than copying it,
+/// which is how [`Min`] and [`Max`] work on elements that aren't `Clone`.
+pub trait Semigroup<T> {
+    /// Combines two aggregates, `left` covering the earlier elements.
+    fn combine(&self, left: &T, right: &T) -> Combined<T>;
+}
+
+/// The result of [`Semigroup::combine`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combined<T> {
+    /// The aggregate is the left operand.
+    Left,
+    /// The aggregate is the right operand.
+    Right,
+    /// The aggregate is a new value.
+    Value(T),
+}
+
+/// Any closure combining two values is a semigroup over those values.
+impl<T, F> Semigroup<T> for F
+where
+    F: Fn(&T, &T) -> T,
+{
+    fn combine(&self, left: &T, right: &T) -> Combined<T> {
+        Combined::Value(self(left, right))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sum;
+
+impl<T> Semigroup<T> for Sum
+where
+    for<'a> &'a T: Add<&'a T, Output = T>,
+{
+    fn combine(&self, left: &T, right: &T) -> Combined<T> {
+        Combined::Value(left + right)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Min;
+
+impl<T: Ord> Semigroup<T> for Min {
+    fn combine(&self, left: &T, right: &T) -> Combined<T> {
+        if right < left {
+            Combined::Right
+        } else {
+            Combined::Left
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Max;
+
+impl<T: Ord> Semigroup<T> for Max {
+    fn combine(&self, left: &T, right: &T) -> Combined<T> {
+        if right > left {
+            Combined::Right
+        } else {
+            Combined::Left
+        }
+    }
 }
 
-impl<T> Stack<T>
+/// Greatest common divisor of unsigned integers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Gcd;
+
+macro_rules! impl_gcd {
+    ($($t:ty),*) => {
+        $(
+            impl Semigroup<$t> for Gcd {
+                fn combine(&self, left: &$t, right: &$t) -> Combined<$t> {
+                    let (mut a, mut b) = (*left, *right);
+                    while b != 0 {
+                        (a, b) = (b, a % b);
+                    }
+                    Combined::Value(a)
+                }
+            }
+        )*
+    };
+}
+
+impl_gcd!(u8, u16, u32, u64, u128, usize);
+
+/// Stack that maintains the aggregate of its elements under a [`Semigroup`].
+/// All operations run in constant time.
+///
+/// An entry only stores a value of its own when combining produced a new
+/// one. Otherwise it points at the element or stored value below it that the
+/// aggregate equals.
+pub struct AggregateStack<T, M: Semigroup<T>> {
+    stack: Vec<AggEntry<T>>,
+    semigroup: M,
+}
+
+struct AggEntry<T> {
+    value: T,
+    // Set when `agg` is `Stored` pointing at this entry.
+    stored: Option<T>,
+    agg: Location,
+}
+
+/// Where the aggregate of an entry and everything below it lives.
+#[derive(Clone, Copy)]
+enum Location {
+    /// The element of the entry at this position.
+    Element(usize),
+    /// The stored value of the entry at this position.
+    Stored(usize),
+}
+
+impl<T, M> AggregateStack<T, M>
 where
-    T: Ord + Clone,
+    M: Semigroup<T> + Default,
 {
     pub fn new() -> Self {
+        Self::with_semigroup(M::default())
+    }
+}
+
+impl<T, M: Semigroup<T>> AggregateStack<T, M> {
+    pub fn with_semigroup(semigroup: M) -> Self {
         Self {
             stack: Vec::new(),
-            min_stack: Vec::new(),
+            semigroup,
         }
     }
 
     pub fn push(&mut self, value: T) {
-        self.stack.push(value.clone());
-        match self.min_stack.last() {
-            Some(last) if &value <= last => self.min_stack.push(value),
-            Some(last) => (),
-            None => self.min_stack.push(value),
-        }
+        let i = self.stack.len();
+        let (agg, stored) = match self.stack.last() {
+            None => (Location::Element(i), None),
+            Some(top) => match self.semigroup.combine(self.resolve(top.agg), &value) {
+                Combined::Left => (top.agg, None),
+                Combined::Right => (Location::Element(i), None),
+                Combined::Value(combined) => (Location::Stored(i), Some(combined)),
+            },
+        };
+        self.stack.push(AggEntry { value, stored, agg });
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        let value = self.stack.pop();
-        if value == self.min_stack.last().cloned() {
-            self.min_stack.pop();
+        self.stack.pop().map(|entry| entry.value)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.stack.last().map(|entry| &entry.value)
+    }
+
+    /// Returns the aggregate of all elements, or `None` if the stack is empty.
+    pub fn aggregate(&self) -> Option<&T> {
+        self.stack.last().map(|top| self.resolve(top.agg))
+    }
+
+    pub fn semigroup(&self) -> &M {
+        &self.semigroup
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    fn resolve(&self, location: Location) -> &T {
+        match location {
+            Location::Element(i) => &self.stack[i].value,
+            Location::Stored(i) => self.stack[i]
+                .stored
+                .as_ref()
+                .expect("entry has a stored aggregate"),
         }
-        value
+    }
+}
+
+/// Stack that supports .push(x), .pop(), .min() and .max(). All operations
+/// run in constant time.
+///
+/// Instead of copies of the extremes, each entry stores the positions of the
+/// minimum and maximum at or below it, so elements don't need to be `Clone`.
+pub struct MinMaxStack<T> {
+    stack: Vec<Entry<T>>,
+}
+
+struct Entry<T> {
+    value: T,
+    min: usize,
+    max: usize,
+}
+
+/// Stack that supports .push(x), .pop(), and .min(),
+/// which returns the minimum element of S. All operations run in constant time.
+pub type Stack<T> = MinMaxStack<T>;
+
+impl<T: Ord> MinMaxStack<T> {
+    pub fn new() -> Self {
+        Self { stack: Vec::new() }
+    }
+
+    pub fn push(&mut self, value: T) {
+        let i = self.stack.len();
+        let (mut min, mut max) = (i, i);
+        if let Some(top) = self.stack.last() {
+            if value >= self.stack[top.min].value {
+                min = top.min;
+            }
+            if value <= self.stack[top.max].value {
+                max = top.max;
+            }
+        }
+        self.stack.push(Entry { value, min, max });
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.stack.pop().map(|entry| entry.value)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.stack.last().map(|entry| &entry.value)
     }
 
     pub fn min(&self) -> Option<&T> {
-        self.min_stack.last()
+        self.stack.last().map(|top| &self.stack[top.min].value)
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.stack.last().map(|top| &self.stack[top.max].value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
     }
 }
 
@@ -90,4 +308,80 @@ mod tests {
         stack.push(2);
         assert_eq!(stack.min(), Some(&2));
     }
+
+    #[test]
+    fn test_max() {
+        // Not `Clone`
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+        struct Value(u32);
+
+        let mut stack = MinMaxStack::new();
+        for i in [4, 9, 1, 9, 6] {
+            stack.push(Value(i));
+        }
+        assert_eq!(stack.len(), 5);
+        assert_eq!(stack.peek(), Some(&Value(6)));
+        assert_eq!(stack.max(), Some(&Value(9)));
+        assert_eq!(stack.min(), Some(&Value(1)));
+
+        stack.pop();
+        stack.pop();
+        stack.pop();
+        assert_eq!(stack.max(), Some(&Value(9)));
+        assert_eq!(stack.min(), Some(&Value(4)));
+        stack.pop();
+        assert_eq!(stack.max(), Some(&Value(4)));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let mut sum: AggregateStack<u32, Sum> = AggregateStack::new();
+        let mut gcd: AggregateStack<u32, Gcd> = AggregateStack::new();
+        assert_eq!(sum.aggregate(), None);
+        for i in [12, 18, 30] {
+            sum.push(i);
+            gcd.push(i);
+        }
+        assert_eq!(sum.aggregate(), Some(&60));
+        assert_eq!(gcd.aggregate(), Some(&6));
+
+        gcd.pop();
+        assert_eq!(gcd.aggregate(), Some(&6));
+        gcd.pop();
+        assert_eq!(gcd.aggregate(), Some(&12));
+
+        // Custom semigroup from a closure
+        let mut product = AggregateStack::with_semigroup(|a: &u64, b: &u64| a * b);
+        for i in 1..=5 {
+            product.push(i);
+        }
+        assert_eq!(product.aggregate(), Some(&120));
+        assert_eq!(product.peek(), Some(&5));
+        assert_eq!(product.len(), 5);
+    }
+
+    #[test]
+    fn test_aggregate_without_clone() {
+        // Not `Clone`
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+        struct Value(u32);
+
+        let mut min: AggregateStack<Value, Min> = AggregateStack::new();
+        let mut max: AggregateStack<Value, Max> = AggregateStack::new();
+        for i in [4, 9, 1, 9, 6] {
+            min.push(Value(i));
+            max.push(Value(i));
+        }
+        assert_eq!(min.aggregate(), Some(&Value(1)));
+        assert_eq!(max.aggregate(), Some(&Value(9)));
+
+        for _ in 0..3 {
+            min.pop();
+            max.pop();
+        }
+        assert_eq!(min.aggregate(), Some(&Value(4)));
+        assert_eq!(max.aggregate(), Some(&Value(9)));
+        max.pop();
+        assert_eq!(max.aggregate(), Some(&Value(4)));
+    }
 }
@@ -11,7 +11,7 @@ pub struct BloomFilter {
 
 impl BloomFilter {
     pub fn with_dimensions(size: usize, hashers_count: usize) -> Self {
-        let bytes_count = size / 8 + if size % 8 > 0 { 1 } else { 0 };
+        let bytes_count = size.div_ceil(8);
         Self {
             size,
             bytes: vec![0; bytes_count],
@@ -31,9 +31,7 @@ impl BloomFilter {
 impl BloomFilter {
     pub fn insert<T: Hash>(&mut self, item: T) {
         for hasher in &self.hashers {
-            let mut hasher = hasher.build_hasher();
-            item.hash(&mut hasher);
-            let hash = hasher.finish();
+            let hash = hasher.hash_one(&item);
             let index = hash % self.size as u64;
 
             let byte_index = index as usize / 8;
@@ -45,9 +43,7 @@ impl BloomFilter {
 
     pub fn contains<T: Hash>(&mut self, item: T) -> bool {
         for hasher in &self.hashers {
-            let mut hasher = hasher.build_hasher();
-            item.hash(&mut hasher);
-            let hash = hasher.finish();
+            let hash = hasher.hash_one(&item);
             let index = hash % self.size as u64;
 
             let byte_index = index as usize / 8;
@@ -50,9 +50,9 @@ where
         }
     }
 
-    fn find(value: T, node: &Box<Node<T>>) -> Option<&Box<Node<T>>> {
+    fn find(value: T, node: &Node<T>) -> Option<&Node<T>> {
         if node.value == value {
-            return Some(node);
+            Some(node)
         } else if node.value > value {
             match node.left {
                 Some(ref n) => Self::find(value, n),
@@ -70,9 +70,7 @@ where
     // https://github.com/rust-lang/rust/issues/54663
     // TODO this version won't work if parent is Tree itself.
     fn find_parent_mut(&mut self, value: T) -> Option<&mut Box<Node<T>>> {
-        if self.root.is_none() {
-            return None;
-        }
+        self.root.as_ref()?;
 
         let mut node = self.root.as_mut().expect("checked by root.is_none()");
         loop {
@@ -147,9 +145,9 @@ where
                         parent.left = left;
                     } else if left.is_none() && right.is_some() {
                         parent.left = right;
-                    } else if left.is_some() && right.is_some() {
+                    } else if let (Some(left), Some(_)) = (left, &right) {
                         parent.left = right;
-                        BSTree::insert_recursive(parent.left.as_mut().unwrap(), left.unwrap());
+                        BSTree::insert_recursive(parent.left.as_mut().unwrap(), left);
                     }
                     return Some(target.value);
                 }
@@ -165,9 +163,9 @@ where
                         parent.right = left;
                     } else if left.is_none() && right.is_some() {
                         parent.right = right;
-                    } else if left.is_some() && right.is_some() {
+                    } else if let (Some(left), Some(_)) = (left, &right) {
                         parent.right = right;
-                        BSTree::insert_recursive(parent.right.as_mut().unwrap(), left.unwrap());
+                        BSTree::insert_recursive(parent.right.as_mut().unwrap(), left);
                     }
                     return Some(target.value);
                 }
@@ -257,19 +255,19 @@ where
         result
     }
 
-    fn traverse_recursive_fn(values: &mut Vec<T>, node: &Box<Node<T>>) {
+    fn traverse_recursive_fn(values: &mut Vec<T>, node: &Node<T>) {
         // For preorder traversal, uncomment:
         // values.push(node.value.clone());
 
         if let Some(ref node) = node.left {
-            BSTree::traverse_recursive_fn(values, &node);
+            BSTree::traverse_recursive_fn(values, node);
         }
 
         // For inorder traversal, uncomment:
         values.push(node.value.clone());
 
         if let Some(ref node) = node.right {
-            BSTree::traverse_recursive_fn(values, &node);
+            BSTree::traverse_recursive_fn(values, node);
         }
 
         // For post order traversal, uncomment:
@@ -322,8 +320,8 @@ where
         result
     }
 
-    pub fn iter(&self) -> Iter<T> {
-        let current = self.root.as_ref().map(|r| r);
+    pub fn iter(&self) -> Iter<'_, T> {
+        let current = self.root.as_deref();
         Iter {
             stack: Vec::new(),
             current,
@@ -332,8 +330,8 @@ where
 }
 
 pub struct Iter<'a, T> {
-    stack: Vec<&'a Box<Node<T>>>,
-    current: Option<&'a Box<Node<T>>>,
+    stack: Vec<&'a Node<T>>,
+    current: Option<&'a Node<T>>,
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
@@ -344,12 +342,12 @@ impl<'a, T> Iterator for Iter<'a, T> {
             (None, q) if q.is_empty() => None,
             (None, q) => {
                 let node = q.pop().expect("guarded by q.is_empty() before");
-                self.current = node.right.as_ref();
+                self.current = node.right.as_deref();
                 Some(&node.value)
             }
             (Some(node), q) => {
                 self.stack.push(node);
-                self.current = node.left.as_ref();
+                self.current = node.left.as_deref();
                 self.next()
             }
         }
@@ -364,7 +362,7 @@ where
     type IntoIter = Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let current = self.root.as_ref();
+        let current = self.root.as_deref();
         Iter {
             stack: Vec::new(),
             current,
@@ -47,9 +47,8 @@ where
 
     pub fn traverse(&self) -> Vec<T> {
         let mut values = Vec::new();
-        match &self.root {
-            Some(root) => Self::traverse_fn(&mut values, root.clone()),
-            None => (),
+        if let Some(root) = &self.root {
+            Self::traverse_fn(&mut values, root.clone())
         }
         values
     }
@@ -75,7 +74,7 @@ where
 
     fn find_fn(node: Link<T>, value: &T) -> Option<Link<T>> {
         if value == &node.borrow().value {
-            return Some(node);
+            Some(node)
         } else if value < &node.borrow().value {
             return match node.borrow().left {
                 Some(ref node) => Self::find_fn(node.clone(), value),
@@ -178,7 +177,7 @@ where
             }
 
             let left = Self::check_valid(left.clone());
-            if left == false {
+            if !left {
                 return false;
             }
         }
@@ -189,12 +188,12 @@ where
             }
 
             let right = Self::check_valid(right.clone());
-            if right == false {
+            if !right {
                 return false;
             }
         }
 
-        return true;
+        true
     }
 }
 
@@ -251,8 +250,8 @@ fn merge_to_ll<T: Ord + Clone>(tree1: &BSTree<T>, tree2: &BSTree<T>) -> List<T>
             current2 = node.borrow().left.as_ref().map(Clone::clone);
         }
 
-        match stack1.get(stack1.len() - 1) {
-            Some(node1) => match stack2.get(stack2.len() - 1) {
+        match stack1.last() {
+            Some(node1) => match stack2.last() {
                 Some(node2) => {
                     if node1.borrow().value < node2.borrow().value {
                         if let Some(node) = stack1.pop() {
@@ -298,6 +297,7 @@ fn merge_to_ll<T: Ord + Clone>(tree1: &BSTree<T>, tree2: &BSTree<T>) -> List<T>
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
     use super::*;
 
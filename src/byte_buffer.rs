@@ -0,0 +1,205 @@
+#![allow(unused)]
+//! A fixed-capacity ring buffer specialised for bytes.
+//!
+//! Unlike [`circular_buffer::Buffer`](crate::circular_buffer::Buffer) it never
+//! overwrites unread data: writes only fill the free space, so it can sit
+//! between a socket and a parser and apply backpressure. Bulk reads and writes
+//! copy at most two contiguous chunks.
+use std::cmp;
+use std::io::{self, BufRead, Read, Write};
+
+const DEFAULT_CAPACITY: usize = 4096;
+
+pub struct ByteBuffer {
+    buf: Box<[u8]>,
+    start: usize,
+    len: usize,
+}
+
+impl ByteBuffer {
+    pub fn new() -> Self {
+        ByteBuffer::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: vec![0; capacity].into_boxed_slice(),
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the total number of bytes the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the number of unread bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of bytes that can be written before the buffer is
+    /// full.
+    pub fn available(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    /// Discards all unread bytes.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    /// Copies as many bytes from `data` as fit into the free space and returns
+    /// how many were written.
+    pub fn write_slice(&mut self, data: &[u8]) -> usize {
+        let n = cmp::min(data.len(), self.available());
+        if n == 0 {
+            return 0;
+        }
+
+        let end = (self.start + self.len) % self.capacity();
+        let first = cmp::min(n, self.capacity() - end);
+        self.buf[end..end + first].copy_from_slice(&data[..first]);
+        self.buf[..n - first].copy_from_slice(&data[first..n]);
+
+        self.len += n;
+        n
+    }
+
+    /// Moves as many unread bytes as fit into `out` and returns how many were
+    /// read.
+    pub fn read_slice(&mut self, out: &mut [u8]) -> usize {
+        let n = cmp::min(out.len(), self.len);
+        if n == 0 {
+            return 0;
+        }
+
+        let first = cmp::min(n, self.capacity() - self.start);
+        out[..first].copy_from_slice(&self.buf[self.start..self.start + first]);
+        out[first..n].copy_from_slice(&self.buf[..n - first]);
+
+        self.consume_bytes(n);
+        n
+    }
+
+    /// Returns a pair of slices which contain, in order, the unread bytes.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        let first = cmp::min(self.len, self.capacity() - self.start);
+        (
+            &self.buf[self.start..self.start + first],
+            &self.buf[..self.len - first],
+        )
+    }
+
+    fn consume_bytes(&mut self, n: usize) {
+        let n = cmp::min(n, self.len);
+        self.len -= n;
+        self.start = if self.len == 0 {
+            // Reset so the next write lands in one contiguous chunk.
+            0
+        } else {
+            (self.start + n) % self.capacity()
+        };
+    }
+}
+
+impl Read for ByteBuffer {
+    /// Reads unread bytes into `buf`. Returns `Ok(0)` when the buffer is empty.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.read_slice(buf))
+    }
+}
+
+impl Write for ByteBuffer {
+    /// Writes into the free space. Returns `Ok(0)` when the buffer is full, so
+    /// `write_all` reports `ErrorKind::WriteZero` instead of overwriting.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.write_slice(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl BufRead for ByteBuffer {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.as_slices().0)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consume_bytes(amt);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ByteBuffer;
+    use std::io::{BufRead, ErrorKind, Read, Write};
+
+    #[test]
+    fn basic() {
+        let mut buf = ByteBuffer::with_capacity(8);
+        assert!(buf.is_empty());
+        assert_eq!(buf.read_slice(&mut [0; 4]), 0);
+
+        assert_eq!(buf.write_slice(b"hello"), 5);
+        assert_eq!(buf.len(), 5);
+        assert_eq!(buf.available(), 3);
+
+        let mut out = [0; 3];
+        assert_eq!(buf.read_slice(&mut out), 3);
+        assert_eq!(&out, b"hel");
+
+        // Wraps around the end of the storage
+        assert_eq!(buf.write_slice(b"world!!"), 6);
+        assert!(buf.is_full());
+        assert_eq!(buf.as_slices(), (&b"lowor"[..], &b"ld!"[..]));
+
+        let mut out = [0; 16];
+        assert_eq!(buf.read_slice(&mut out), 8);
+        assert_eq!(&out[..8], b"loworld!");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn io() {
+        let mut buf = ByteBuffer::with_capacity(16);
+        write!(buf, "line one\nline").unwrap();
+
+        let mut line = String::new();
+        buf.read_line(&mut line).unwrap();
+        assert_eq!(line, "line one\n");
+
+        buf.write_all(b" two\n").unwrap();
+        let mut rest = String::new();
+        buf.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "line two\n");
+
+        let err = buf.write_all(&[0; 17]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn fill_buf() {
+        let mut buf = ByteBuffer::with_capacity(4);
+        buf.write_slice(b"abc");
+        buf.consume(2);
+        buf.write_slice(b"def");
+        assert_eq!(buf.fill_buf().unwrap(), b"cd");
+        buf.consume(2);
+        assert_eq!(buf.fill_buf().unwrap(), b"ef");
+        buf.consume(10);
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,596 @@
+#![allow(unused)]
+//! A bounded multi-producer multi-consumer channel.
+//!
+//! Messages are stored in a [`circular_buffer::Buffer`](crate::circular_buffer::Buffer)
+//! guarded by a mutex. Blocking calls wait on condition variables, async calls
+//! return futures that park their waker in the channel until there is room or
+//! a message. Each pending future keeps at most one waker parked and takes it
+//! back when dropped. No async runtime is required. The channel is
+//! disconnected once all senders or all receivers are dropped.
+use crate::circular_buffer::Buffer;
+use crate::gen_arena::{GenArena, Index};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// The message could not be sent because all receivers are gone. Holds the
+/// message back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    Timeout(T),
+    Disconnected(T),
+}
+
+/// The channel is empty and all senders are gone.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected,
+}
+
+struct State<T> {
+    buf: Buffer<T>,
+    capacity: usize,
+    senders: usize,
+    receivers: usize,
+    // Wakers of pending futures. A future's handle goes stale once its waker
+    // has been woken.
+    send_wakers: GenArena<Waker>,
+    recv_wakers: GenArena<Waker>,
+}
+
+impl<T> State<T> {
+    fn is_full(&self) -> bool {
+        self.buf.len() == self.capacity
+    }
+}
+
+/// Parks `waker` for the future whose entry is `slot`, replacing its previous
+/// waker if that hasn't been woken yet.
+fn park(wakers: &mut GenArena<Waker>, slot: &mut Option<Index>, waker: &Waker) {
+    match slot.and_then(|index| wakers.get_mut(index)) {
+        Some(parked) => {
+            if !parked.will_wake(waker) {
+                parked.clone_from(waker);
+            }
+        }
+        None => *slot = Some(wakers.insert(waker.clone())),
+    }
+}
+
+/// Takes the future's waker back out, if it's still parked.
+fn unpark(wakers: &mut GenArena<Waker>, slot: &mut Option<Index>) {
+    if let Some(index) = slot.take() {
+        wakers.remove(index);
+    }
+}
+
+/// Wakes every parked future, leaving their handles stale.
+fn wake_all(wakers: &mut GenArena<Waker>) {
+    wakers.retain(|_, waker| {
+        waker.wake_by_ref();
+        false
+    });
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap()
+    }
+
+    /// Pushes a message and wakes up a waiting receiver.
+    fn push(&self, state: &mut State<T>, value: T) {
+        state.buf.push(value);
+        self.not_empty.notify_one();
+        wake_all(&mut state.recv_wakers);
+    }
+
+    /// Pops a message and wakes up a waiting sender.
+    fn pop(&self, state: &mut State<T>) -> Option<T> {
+        let value = state.buf.pop()?;
+        self.not_full.notify_one();
+        wake_all(&mut state.send_wakers);
+        Some(value)
+    }
+}
+
+/// The sending half of a channel. Can be cloned to send from many threads.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of a channel. Can be cloned to receive from many threads.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Creates a channel that buffers up to `capacity` messages.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "channel capacity must be non-zero");
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            // The ring keeps one slot free, so ask for one more.
+            buf: Buffer::with_capacity(capacity + 1),
+            capacity,
+            senders: 1,
+            receivers: 1,
+            send_wakers: GenArena::new(),
+            recv_wakers: GenArena::new(),
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+impl<T> Sender<T> {
+    /// Sends a message, blocking while the channel is full.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.lock();
+        loop {
+            if state.receivers == 0 {
+                return Err(SendError(value));
+            }
+            if !state.is_full() {
+                break;
+            }
+            state = self.shared.not_full.wait(state).unwrap();
+        }
+        self.shared.push(&mut state, value);
+        Ok(())
+    }
+
+    /// Sends a message if there is room, without blocking.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut state = self.shared.lock();
+        if state.receivers == 0 {
+            Err(TrySendError::Disconnected(value))
+        } else if state.is_full() {
+            Err(TrySendError::Full(value))
+        } else {
+            self.shared.push(&mut state, value);
+            Ok(())
+        }
+    }
+
+    /// Sends a message, blocking for at most `timeout` while the channel is
+    /// full.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if state.receivers == 0 {
+                return Err(SendTimeoutError::Disconnected(value));
+            }
+            if !state.is_full() {
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(SendTimeoutError::Timeout(value));
+            }
+            state = self
+                .shared
+                .not_full
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+        self.shared.push(&mut state, value);
+        Ok(())
+    }
+
+    /// Returns a future that resolves once the message is sent.
+    pub fn send_async(&self, value: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            value: Some(value),
+            waker: None,
+        }
+    }
+
+    /// Returns `true` if all receivers have been dropped.
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.lock().receivers == 0
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receives a message, blocking while the channel is empty. Messages sent
+    /// before the last sender was dropped are still delivered.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(value) = self.shared.pop(&mut state) {
+                return Ok(value);
+            }
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+            state = self.shared.not_empty.wait(state).unwrap();
+        }
+    }
+
+    /// Receives a message if one is available, without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.lock();
+        match self.shared.pop(&mut state) {
+            Some(value) => Ok(value),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receives a message, blocking for at most `timeout` while the channel is
+    /// empty.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(value) = self.shared.pop(&mut state) {
+                return Ok(value);
+            }
+            if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self
+                .shared
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Returns a future that resolves to the next message.
+    pub fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            waker: None,
+        }
+    }
+
+    /// Returns `true` if all senders have been dropped.
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.lock().senders == 0
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().receivers += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            self.shared.not_empty.notify_all();
+            wake_all(&mut state.recv_wakers);
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            self.shared.not_full.notify_all();
+            wake_all(&mut state.send_wakers);
+        }
+    }
+}
+
+/// Future returned by [`Sender::send_async`].
+pub struct SendFuture<'a, T> {
+    sender: &'a Sender<T>,
+    value: Option<T>,
+    waker: Option<Index>,
+}
+
+// The message is never pinned, it is moved into the channel.
+impl<T> Unpin for SendFuture<'_, T> {}
+
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut state = this.sender.shared.lock();
+        let value = this.value.take().expect("polled after completion");
+        if state.receivers == 0 {
+            unpark(&mut state.send_wakers, &mut this.waker);
+            Poll::Ready(Err(SendError(value)))
+        } else if state.is_full() {
+            park(&mut state.send_wakers, &mut this.waker, cx.waker());
+            this.value = Some(value);
+            Poll::Pending
+        } else {
+            unpark(&mut state.send_wakers, &mut this.waker);
+            this.sender.shared.push(&mut state, value);
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        if self.waker.is_some() {
+            let mut state = self.sender.shared.lock();
+            unpark(&mut state.send_wakers, &mut self.waker);
+        }
+    }
+}
+
+/// Future returned by [`Receiver::recv_async`].
+pub struct RecvFuture<'a, T> {
+    receiver: &'a Receiver<T>,
+    waker: Option<Index>,
+}
+
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let shared = &this.receiver.shared;
+        let mut state = shared.lock();
+        let result = match shared.pop(&mut state) {
+            Some(value) => Ok(value),
+            None if state.senders == 0 => Err(RecvError),
+            None => {
+                park(&mut state.recv_wakers, &mut this.waker, cx.waker());
+                return Poll::Pending;
+            }
+        };
+        unpark(&mut state.recv_wakers, &mut this.waker);
+        Poll::Ready(result)
+    }
+}
+
+impl<T> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        if self.waker.is_some() {
+            let mut state = self.receiver.shared.lock();
+            unpark(&mut state.recv_wakers, &mut self.waker);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::thread;
+
+    /// Minimal executor that parks the current thread until woken.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct ThreadWaker(thread::Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn basic() {
+        let (tx, rx) = channel(2);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        tx.send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(
+            tx.send_timeout(3, Duration::from_millis(10)),
+            Err(SendTimeoutError::Timeout(3))
+        );
+
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    }
+
+    #[test]
+    fn disconnect() {
+        let (tx, rx) = channel(4);
+        let tx2 = tx.clone();
+        tx.send(1).unwrap();
+        drop(tx);
+        assert!(!rx.is_disconnected());
+        tx2.send(2).unwrap();
+        drop(tx2);
+
+        assert!(rx.is_disconnected());
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.recv(), Ok(2));
+        assert_eq!(rx.recv(), Err(RecvError));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+        let (tx, rx) = channel(1);
+        drop(rx);
+        assert_eq!(tx.send(1), Err(SendError(1)));
+        assert_eq!(tx.try_send(2), Err(TrySendError::Disconnected(2)));
+    }
+
+    #[test]
+    fn blocking_wakes_up() {
+        let (tx, rx) = channel(1);
+        tx.send(0).unwrap();
+
+        let handle = thread::spawn(move || {
+            // Blocks until the receiver makes room, then until it disconnects.
+            tx.send(1).unwrap();
+            while tx.send(2).is_ok() {}
+        });
+
+        assert_eq!(rx.recv(), Ok(0));
+        assert_eq!(rx.recv(), Ok(1));
+        drop(rx);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn mpmc() {
+        const PER_SENDER: usize = 1000;
+        let (tx, rx) = channel(8);
+        let total = Arc::new(AtomicUsize::new(0));
+
+        let senders: Vec<_> = (0..4)
+            .map(|_| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_SENDER {
+                        tx.send(i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+
+        let receivers: Vec<_> = (0..3)
+            .map(|_| {
+                let rx = rx.clone();
+                let total = total.clone();
+                thread::spawn(move || {
+                    while let Ok(value) = rx.recv() {
+                        total.fetch_add(value, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        drop(rx);
+
+        senders.into_iter().for_each(|h| h.join().unwrap());
+        receivers.into_iter().for_each(|h| h.join().unwrap());
+        let expected = 4 * PER_SENDER * (PER_SENDER - 1) / 2;
+        assert_eq!(total.load(Ordering::Relaxed), expected);
+    }
+
+    #[test]
+    fn async_send_recv() {
+        let (tx, rx) = channel(2);
+
+        let handle = thread::spawn(move || {
+            block_on(async {
+                for i in 0..100 {
+                    tx.send_async(i).await.unwrap();
+                }
+            })
+        });
+
+        let received = block_on(async {
+            let mut received = Vec::new();
+            while let Ok(value) = rx.recv_async().await {
+                received.push(value);
+            }
+            received
+        });
+
+        handle.join().unwrap();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn async_wakers_dont_pile_up() {
+        struct Noop;
+
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+        let (tx, rx) = channel(1);
+
+        let mut recv = rx.recv_async();
+        for _ in 0..10 {
+            assert!(Pin::new(&mut recv).poll(&mut cx).is_pending());
+        }
+        assert_eq!(rx.shared.lock().recv_wakers.len(), 1);
+        drop(recv);
+        assert!(rx.shared.lock().recv_wakers.is_empty());
+
+        tx.send(0).unwrap();
+        let mut send = tx.send_async(1);
+        for _ in 0..10 {
+            assert!(Pin::new(&mut send).poll(&mut cx).is_pending());
+        }
+        assert_eq!(tx.shared.lock().send_wakers.len(), 1);
+        // Making room wakes the sender and clears its entry.
+        assert_eq!(rx.try_recv(), Ok(0));
+        assert!(tx.shared.lock().send_wakers.is_empty());
+        assert_eq!(Pin::new(&mut send).poll(&mut cx), Poll::Ready(Ok(())));
+        drop(send);
+        assert_eq!(rx.try_recv(), Ok(1));
+    }
+}
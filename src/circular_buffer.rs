@@ -1,9 +1,17 @@
 #![allow(unused)]
+use std::mem::MaybeUninit;
+use std::ops::{Index, IndexMut};
+use std::{ptr, slice};
 
 const DEFAULT_CAPACITY: usize = 5;
 
+/// A fixed-size ring buffer that overwrites the oldest element when full.
+///
+/// One slot is always kept free to tell a full buffer from an empty one, so a
+/// buffer with capacity `n` holds at most `n - 1` elements. Slots between
+/// `tail` (oldest) and `head` (next write) are initialized, all others are not.
 pub struct Buffer<T> {
-    buf: Vec<Option<T>>,
+    buf: Box<[MaybeUninit<T>]>,
     head: usize,
     tail: usize,
 }
@@ -14,10 +22,10 @@ impl<T> Buffer<T> {
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
-        let mut buf = Vec::new();
-        buf.resize_with(capacity, Default::default);
+        let mut buf = Vec::with_capacity(capacity);
+        buf.resize_with(capacity, MaybeUninit::uninit);
         Self {
-            buf,
+            buf: buf.into_boxed_slice(),
             head: 0,
             tail: 0,
         }
@@ -29,12 +37,13 @@ impl<T> Buffer<T> {
             next = 0;
         }
 
-        self.buf[self.head] = Some(value);
+        self.buf[self.head].write(value);
         self.head = next;
 
         if next == self.tail {
-            // buffer if full, move the tail
-            self.tail += 1;
+            // buffer if full, drop the oldest element and move the tail
+            unsafe { self.buf[self.tail].assume_init_drop() };
+            self.tail = self.wrap_add(self.tail, 1);
         }
     }
 
@@ -43,7 +52,7 @@ impl<T> Buffer<T> {
             return None;
         }
 
-        let result = self.buf[self.tail].take();
+        let result = unsafe { self.buf[self.tail].assume_init_read() };
 
         let mut next = self.tail + 1;
         if next >= self.capacity() {
@@ -51,12 +60,258 @@ impl<T> Buffer<T> {
         }
         self.tail = next;
 
-        result
+        Some(result)
+    }
+
+    /// Returns the number of elements in the buffer.
+    pub fn len(&self) -> usize {
+        if self.head >= self.tail {
+            self.head - self.tail
+        } else {
+            self.capacity() - self.tail + self.head
+        }
+    }
+
+    /// Returns `true` if the buffer contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    /// Returns a reference to the element at index `i`, counting from the
+    /// oldest element, or `None` if out of bounds.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len() {
+            return None;
+        }
+        let index = self.wrap_add(self.tail, i);
+        Some(unsafe { self.buf[index].assume_init_ref() })
+    }
+
+    /// Returns a mutable reference to the element at index `i`, counting from
+    /// the oldest element, or `None` if out of bounds.
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        if i >= self.len() {
+            return None;
+        }
+        let index = self.wrap_add(self.tail, i);
+        Some(unsafe { self.buf[index].assume_init_mut() })
+    }
+
+    /// Provides a reference to the oldest element, or `None` if the buffer is
+    /// empty.
+    pub fn peek_front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Provides a reference to the most recently pushed element, or `None` if
+    /// the buffer is empty.
+    pub fn peek_back(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    /// Returns a pair of slices which contain, in order, the contents of the
+    /// buffer from oldest to newest.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (front, back) = self.slice_ranges();
+        unsafe {
+            (
+                slice_assume_init(&self.buf[front.0..front.1]),
+                slice_assume_init(&self.buf[back.0..back.1]),
+            )
+        }
+    }
+
+    /// Returns a pair of mutable slices which contain, in order, the contents
+    /// of the buffer from oldest to newest.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (front, back) = self.slice_ranges();
+        let (rest, front_slice) = self.buf.split_at_mut(front.0);
+        let front_slice = &mut front_slice[..front.1 - front.0];
+        let back_slice = &mut rest[back.0..back.1];
+        unsafe {
+            (
+                slice_assume_init_mut(front_slice),
+                slice_assume_init_mut(back_slice),
+            )
+        }
+    }
+
+    /// Rearranges the internal storage so the elements are contiguous and
+    /// returns them as a single mutable slice, oldest first.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.tail > self.head {
+            self.buf.rotate_left(self.tail);
+            self.head = self.len();
+            self.tail = 0;
+        }
+        self.as_mut_slices().0
+    }
+
+    /// Returns a front-to-back iterator, from oldest to newest element.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (front, back) = self.as_slices();
+        Iter {
+            front: front.iter(),
+            back: back.iter(),
+        }
+    }
+
+    /// Returns a front-to-back iterator that returns mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (front, back) = self.as_mut_slices();
+        IterMut {
+            front: front.iter_mut(),
+            back: back.iter_mut(),
+        }
+    }
+
+    /// Removes all elements from the buffer and returns them as an iterator,
+    /// oldest first. Elements not consumed by the iterator are dropped.
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        Drain(self)
     }
 
     fn capacity(&self) -> usize {
         self.buf.len()
     }
+
+    fn wrap_add(&self, index: usize, n: usize) -> usize {
+        (index + n) % self.capacity()
+    }
+
+    /// Returns the `[start, end)` slot ranges of the two initialized halves.
+    fn slice_ranges(&self) -> ((usize, usize), (usize, usize)) {
+        if self.tail <= self.head {
+            ((self.tail, self.head), (0, 0))
+        } else {
+            ((self.tail, self.capacity()), (0, self.head))
+        }
+    }
+}
+
+unsafe fn slice_assume_init<T>(slice: &[MaybeUninit<T>]) -> &[T] {
+    &*(slice as *const [MaybeUninit<T>] as *const [T])
+}
+
+unsafe fn slice_assume_init_mut<T>(slice: &mut [MaybeUninit<T>]) -> &mut [T] {
+    &mut *(slice as *mut [MaybeUninit<T>] as *mut [T])
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        unsafe {
+            ptr::drop_in_place(front);
+            ptr::drop_in_place(back);
+        }
+    }
+}
+
+impl<T> Index<usize> for Buffer<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("Out of bounds access")
+    }
+}
+
+impl<T> IndexMut<usize> for Buffer<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        self.get_mut(i).expect("Out of bounds access")
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: slice::Iter<'a, T>,
+    back: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    front: slice::IterMut<'a, T>,
+    back: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Buffer<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Buffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct Drain<'a, T>(&'a mut Buffer<T>);
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        while self.0.pop().is_some() {}
+    }
 }
 
 #[cfg(test)]
@@ -84,4 +339,90 @@ mod test {
         buf.push(1);
         assert_eq!(buf.pop(), Some(1));
     }
+
+    #[test]
+    fn overwrite_wraps_tail() {
+        let mut buf = Buffer::with_capacity(3);
+        for i in 0..10 {
+            buf.push(i);
+        }
+        assert_eq!(buf.len(), 2);
+        assert_eq!(buf.pop(), Some(8));
+        assert_eq!(buf.pop(), Some(9));
+        assert_eq!(buf.pop(), None);
+    }
+
+    #[test]
+    fn access() {
+        let mut buf = Buffer::new();
+        assert_eq!(buf.peek_front(), None);
+        assert_eq!(buf.peek_back(), None);
+        assert_eq!(buf.get(0), None);
+
+        for i in 0..6 {
+            buf.push(i);
+        }
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.peek_front(), Some(&2));
+        assert_eq!(buf.peek_back(), Some(&5));
+        assert_eq!(buf.get(1), Some(&3));
+        assert_eq!(buf.get(4), None);
+
+        buf[0] = 20;
+        *buf.get_mut(3).unwrap() = 50;
+        assert_eq!(buf[0], 20);
+        assert_eq!(buf[3], 50);
+    }
+
+    #[test]
+    fn iter() {
+        let mut buf = Buffer::new();
+        for i in 0..7 {
+            buf.push(i);
+        }
+        assert_eq!(buf.iter().collect::<Vec<_>>(), vec![&3, &4, &5, &6]);
+        assert_eq!(buf.iter().rev().collect::<Vec<_>>(), vec![&6, &5, &4, &3]);
+        assert_eq!(buf.iter().len(), 4);
+
+        for value in &mut buf {
+            *value *= 10;
+        }
+        assert_eq!(buf.iter().collect::<Vec<_>>(), vec![&30, &40, &50, &60]);
+        assert_eq!(buf.len(), 4);
+    }
+
+    #[test]
+    fn slices() {
+        let mut buf = Buffer::new();
+        for i in 0..7 {
+            buf.push(i);
+        }
+        assert_eq!(buf.as_slices(), (&[3, 4][..], &[5, 6][..]));
+
+        assert_eq!(buf.make_contiguous(), &[3, 4, 5, 6]);
+        assert_eq!(buf.as_slices(), (&[3, 4, 5, 6][..], &[][..]));
+
+        buf.push(7);
+        assert_eq!(buf.as_slices(), (&[4, 5, 6, 7][..], &[][..]));
+        assert_eq!(buf.pop(), Some(4));
+    }
+
+    #[test]
+    fn drain() {
+        let mut buf = Buffer::new();
+        for i in 0..7 {
+            buf.push(i.to_string());
+        }
+        let drained: Vec<_> = buf.drain().collect();
+        assert_eq!(drained, vec!["3", "4", "5", "6"]);
+        assert!(buf.is_empty());
+
+        buf.push("a".to_string());
+        buf.push("b".to_string());
+        let mut drain = buf.drain();
+        assert_eq!(drain.next().as_deref(), Some("a"));
+        drop(drain);
+        assert!(buf.is_empty());
+        assert_eq!(buf.pop(), None);
+    }
 }
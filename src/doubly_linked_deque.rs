@@ -1,7 +1,8 @@
 #![allow(unused)]
 use std::{
     cell::{Ref, RefCell, RefMut},
-    rc::Rc,
+    mem,
+    rc::{Rc, Weak},
 };
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
@@ -9,16 +10,34 @@ type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 /// A doubly-linked list.
 ///
 /// The `LinkedList` allows pushing and popping elements at either end
-/// in constant time.
-struct LinkedList<T> {
+/// in constant time. Cursors can insert and remove anywhere in the list in
+/// constant time as well.
+///
+/// Every node points at an owner token for the list it's in, so handles to
+/// another list's elements can be told apart. Appending and splicing forward
+/// one list's token to the other's, union-find style, and stay *O*(1).
+/// Splitting gives the shorter part a fresh token, which costs
+/// *O*(min(i, n - i)).
+pub struct LinkedList<T> {
     head: Link<T>,
     tail: Link<T>,
+    len: usize,
+    // `None` until the list first holds an element. Never forwarded.
+    owner: Option<Rc<Owner>>,
 }
 
 struct Node<T> {
     next: Link<T>,
     prev: Link<T>,
     element: T,
+    owner: Rc<Owner>,
+}
+
+/// Identifies a list. A token that's been merged into another list forwards
+/// to that list's token.
+#[derive(Default)]
+struct Owner {
+    forward: RefCell<Option<Rc<Owner>>>,
 }
 
 impl<T> LinkedList<T> {
@@ -27,45 +46,54 @@ impl<T> LinkedList<T> {
         Self {
             head: None,
             tail: None,
+            len: 0,
+            owner: None,
         }
     }
 
+    /// Returns the number of elements in the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     /// Adds an element first in the list.
     pub fn push_front(&mut self, elt: T) {
-        let new_head = Node::new(elt);
-        match self.head.take() {
-            Some(old_head) => {
-                old_head.borrow_mut().prev = Some(new_head.clone());
-                new_head.borrow_mut().next = Some(old_head);
-                self.head = Some(new_head);
-            }
-            None => {
-                self.tail = Some(new_head.clone());
-                self.head = Some(new_head);
-            }
-        }
+        let node = Node::new(elt, self.owner());
+        self.link_front(node);
     }
 
     /// Appends an element to the back of a list.
     pub fn push_back(&mut self, elt: T) {
-        let new_node = Node::new(elt);
-        match self.tail.take() {
-            Some(old_node) => {
-                old_node.borrow_mut().next = Some(new_node.clone());
-                new_node.borrow_mut().prev = Some(old_node);
-                self.tail = Some(new_node);
-            }
-            None => {
-                self.tail = Some(new_node.clone());
-                self.head = Some(new_node);
-            }
-        }
+        let node = Node::new(elt, self.owner());
+        self.link_back(node);
+    }
+
+    /// Adds an element first in the list and returns a handle to it.
+    pub fn push_front_handle(&mut self, elt: T) -> NodeHandle<T> {
+        let node = Node::new(elt, self.owner());
+        let handle = NodeHandle(Rc::downgrade(&node));
+        self.link_front(node);
+        handle
+    }
+
+    /// Appends an element to the back of a list and returns a handle to it.
+    pub fn push_back_handle(&mut self, elt: T) -> NodeHandle<T> {
+        let node = Node::new(elt, self.owner());
+        let handle = NodeHandle(Rc::downgrade(&node));
+        self.link_back(node);
+        handle
     }
 
     /// Removes the first element and returns it, or `None` if the list is
     /// empty.
     pub fn pop_front(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
+            self.len -= 1;
             match old_head.borrow_mut().next.take() {
                 Some(new_head) => {
                     new_head.borrow_mut().prev.take();
@@ -75,7 +103,11 @@ impl<T> LinkedList<T> {
                     self.tail.take();
                 }
             }
-            Rc::try_unwrap(old_head).ok().unwrap().into_inner().element
+            Rc::try_unwrap(old_head)
+                .ok()
+                .expect("only the list holds strong references to its nodes")
+                .into_inner()
+                .element
         })
     }
 
@@ -83,6 +115,7 @@ impl<T> LinkedList<T> {
     /// it is empty.
     pub fn pop_back(&mut self) -> Option<T> {
         self.tail.take().map(|old_tail| {
+            self.len -= 1;
             match old_tail.borrow_mut().prev.take() {
                 Some(new_tail) => {
                     new_tail.borrow_mut().next.take();
@@ -92,13 +125,17 @@ impl<T> LinkedList<T> {
                     self.head.take();
                 }
             }
-            Rc::try_unwrap(old_tail).ok().unwrap().into_inner().element
+            Rc::try_unwrap(old_tail)
+                .ok()
+                .expect("only the list holds strong references to its nodes")
+                .into_inner()
+                .element
         })
     }
 
     /// Provides a reference to the front element, or `None` if the list is
     /// empty.
-    pub fn front(&self) -> Option<Ref<T>> {
+    pub fn front(&self) -> Option<Ref<'_, T>> {
         self.head
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.element))
@@ -106,7 +143,7 @@ impl<T> LinkedList<T> {
 
     /// Provides a reference to the back element, or `None` if the list is
     /// empty.
-    pub fn back(&self) -> Option<Ref<T>> {
+    pub fn back(&self) -> Option<Ref<'_, T>> {
         self.tail
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.element))
@@ -114,7 +151,7 @@ impl<T> LinkedList<T> {
 
     /// Provides a mutable reference to the front element, or `None` if the list
     /// is empty.
-    pub fn front_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn front_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.head
             .as_mut()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.element))
@@ -122,7 +159,7 @@ impl<T> LinkedList<T> {
 
     /// Provides a mutable reference to the back element, or `None` if the list
     /// is empty.
-    pub fn back_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn back_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.tail
             .as_mut()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.element))
@@ -132,6 +169,600 @@ impl<T> LinkedList<T> {
     pub fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
+
+    /// Removes all elements from the list.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns `true` if the list contains an element equal to `x`.
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut node = self.head.clone();
+        while let Some(current) = node {
+            let current = current.borrow();
+            if current.element == *x {
+                return true;
+            }
+            node = current.next.clone();
+        }
+        false
+    }
+
+    /// Folds every element into an accumulator, front to back.
+    ///
+    /// Nodes sit behind `RefCell`s, so a reference to an element can't outlive
+    /// the borrow of its node. Rather than an iterator, each node is borrowed
+    /// in turn for the duration of one call to `f`.
+    pub fn fold<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &T) -> B,
+    {
+        let mut acc = init;
+        let mut node = self.head.clone();
+        while let Some(current) = node {
+            let current = current.borrow();
+            acc = f(acc, &current.element);
+            node = current.next.clone();
+        }
+        acc
+    }
+
+    /// Folds every element into an accumulator, back to front.
+    pub fn rfold<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &T) -> B,
+    {
+        let mut acc = init;
+        let mut node = self.tail.clone();
+        while let Some(current) = node {
+            let current = current.borrow();
+            acc = f(acc, &current.element);
+            node = current.prev.clone();
+        }
+        acc
+    }
+
+    /// Calls `f` on every element, front to back.
+    pub fn for_each<F: FnMut(&T)>(&self, mut f: F) {
+        self.fold((), |(), x| f(x));
+    }
+
+    /// Calls `f` on every element, back to front.
+    pub fn rfor_each<F: FnMut(&T)>(&self, mut f: F) {
+        self.rfold((), |(), x| f(x));
+    }
+
+    /// Calls `f` on a mutable reference to every element, front to back.
+    pub fn for_each_mut<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        let mut node = self.head.clone();
+        while let Some(current) = node {
+            let mut current = current.borrow_mut();
+            f(&mut current.element);
+            node = current.next.clone();
+        }
+    }
+
+    /// Removes the element `handle` points to and returns it, or `None` if
+    /// it has already been removed or is in another list. This runs in
+    /// *O*(1).
+    ///
+    /// Handles follow their element if it's moved to another list, by
+    /// `append`, `split_off` or a cursor.
+    pub fn remove(&mut self, handle: &NodeHandle<T>) -> Option<T> {
+        let node = self.node_of(handle)?;
+        Some(self.unlink(node))
+    }
+
+    /// Moves the element `handle` points to to the front of the list. Returns
+    /// `false` if it has already been removed or is in another list. This
+    /// runs in *O*(1).
+    pub fn move_to_front(&mut self, handle: &NodeHandle<T>) -> bool {
+        let Some(node) = self.node_of(handle) else {
+            return false;
+        };
+        self.detach(&node);
+        self.link_front(node);
+        true
+    }
+
+    /// Moves the element `handle` points to to the back of the list. Returns
+    /// `false` if it has already been removed or is in another list. This
+    /// runs in *O*(1).
+    pub fn move_to_back(&mut self, handle: &NodeHandle<T>) -> bool {
+        let Some(node) = self.node_of(handle) else {
+            return false;
+        };
+        self.detach(&node);
+        self.link_back(node);
+        true
+    }
+
+    /// Moves all elements from `other` to the end of the list, leaving
+    /// `other` empty. This runs in *O*(1).
+    pub fn append(&mut self, other: &mut Self) {
+        let tail = self.tail.clone();
+        self.splice_between(tail, None, mem::take(other));
+    }
+
+    /// Splits the list into two at the given index. Returns everything after
+    /// the given index, including the index. This runs in
+    /// *O*(min(at, n - at)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "cannot split off at a nonexistent index");
+        if at == 0 {
+            return mem::take(self);
+        }
+        let last = self.node_at(at - 1);
+        self.split_after_node(Some(last), at)
+    }
+
+    /// Provides a cursor at the front element, or at the "ghost" position if
+    /// the list is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.head.as_ref().map(Rc::downgrade),
+            index: 0,
+            list: self,
+        }
+    }
+
+    /// Provides a cursor at the back element, or at the "ghost" position if
+    /// the list is empty.
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.tail.as_ref().map(Rc::downgrade),
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+
+    /// Provides a cursor with editing operations at the front element.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head.as_ref().map(Rc::downgrade),
+            index: 0,
+            list: self,
+        }
+    }
+
+    /// Provides a cursor with editing operations at the back element.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail.as_ref().map(Rc::downgrade),
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+
+    /// Returns the list's owner token, creating one if it doesn't have one
+    /// yet.
+    fn owner(&mut self) -> Rc<Owner> {
+        self.owner.get_or_insert_with(Rc::default).clone()
+    }
+
+    /// Returns the node `handle` points to if it's still in this list.
+    fn node_of(&self, handle: &NodeHandle<T>) -> Link<T> {
+        let owner = self.owner.as_ref()?;
+        let node = handle.0.upgrade()?;
+        let root = Owner::root(&node.borrow().owner);
+        Rc::ptr_eq(&root, owner).then_some(node)
+    }
+
+    /// Points `node` and every node after it at `owner`.
+    fn relabel(mut node: Link<T>, owner: &Rc<Owner>) {
+        while let Some(current) = node {
+            let mut current = current.borrow_mut();
+            current.owner = owner.clone();
+            node = current.next.clone();
+        }
+    }
+
+    /// Returns the node at index `i`, walking from the nearer end.
+    fn node_at(&self, i: usize) -> Rc<RefCell<Node<T>>> {
+        let (mut node, steps, forward) = if i <= self.len / 2 {
+            (self.head.clone(), i, true)
+        } else {
+            (self.tail.clone(), self.len - 1 - i, false)
+        };
+        for _ in 0..steps {
+            node = node.and_then(|node| {
+                let node = node.borrow();
+                if forward {
+                    node.next.clone()
+                } else {
+                    node.prev.clone()
+                }
+            });
+        }
+        node.expect("index out of bounds")
+    }
+
+    /// Links all of `other` in between two adjacent positions: `prev` and
+    /// `next` are neighbours in the list, or `None` at either end.
+    fn splice_between(&mut self, prev: Link<T>, next: Link<T>, mut other: Self) {
+        let (Some(head), Some(tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        let theirs = other.owner.take().expect("a non-empty list has an owner");
+        self.owner = Some(match self.owner.take() {
+            Some(ours) if self.len >= other.len => {
+                *theirs.forward.borrow_mut() = Some(ours.clone());
+                ours
+            }
+            Some(ours) => {
+                *ours.forward.borrow_mut() = Some(theirs.clone());
+                theirs
+            }
+            None => theirs,
+        });
+        self.len += mem::take(&mut other.len);
+
+        match prev {
+            Some(prev) => {
+                head.borrow_mut().prev = Some(prev.clone());
+                prev.borrow_mut().next = Some(head);
+            }
+            None => self.head = Some(head),
+        }
+        match next {
+            Some(next) => {
+                tail.borrow_mut().next = Some(next.clone());
+                next.borrow_mut().prev = Some(tail);
+            }
+            None => self.tail = Some(tail),
+        }
+    }
+
+    /// Cuts the list after `last`, which becomes the new tail, and returns
+    /// the rest. `len_before` is the number of elements up to and including
+    /// `last`. If `last` is `None`, the whole list is returned.
+    fn split_after_node(&mut self, last: Link<T>, len_before: usize) -> Self {
+        let Some(last) = last else {
+            return mem::take(self);
+        };
+        let Some(first) = last.borrow_mut().next.take() else {
+            return Self::new();
+        };
+        first.borrow_mut().prev = None;
+
+        let tail = self.tail.replace(last);
+        let mut rest = Self {
+            head: Some(first),
+            tail,
+            len: self.len - len_before,
+            owner: Some(Rc::default()),
+        };
+        self.len = len_before;
+        if rest.len > self.len {
+            mem::swap(&mut self.owner, &mut rest.owner);
+            Self::relabel(self.head.clone(), &self.owner());
+        } else {
+            Self::relabel(rest.head.clone(), &rest.owner());
+        }
+        rest
+    }
+
+    /// Links a detached node in as the new head.
+    fn link_front(&mut self, new_head: Rc<RefCell<Node<T>>>) {
+        self.len += 1;
+        match self.head.take() {
+            Some(old_head) => {
+                old_head.borrow_mut().prev = Some(new_head.clone());
+                new_head.borrow_mut().next = Some(old_head);
+                self.head = Some(new_head);
+            }
+            None => {
+                self.tail = Some(new_head.clone());
+                self.head = Some(new_head);
+            }
+        }
+    }
+
+    /// Links a detached node in as the new tail.
+    fn link_back(&mut self, new_node: Rc<RefCell<Node<T>>>) {
+        self.len += 1;
+        match self.tail.take() {
+            Some(old_node) => {
+                old_node.borrow_mut().next = Some(new_node.clone());
+                new_node.borrow_mut().prev = Some(old_node);
+                self.tail = Some(new_node);
+            }
+            None => {
+                self.tail = Some(new_node.clone());
+                self.head = Some(new_node);
+            }
+        }
+    }
+
+    /// Takes `node` out of the list, leaving it with no neighbours.
+    fn detach(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let (prev, next) = {
+            let mut node = node.borrow_mut();
+            (node.prev.take(), node.next.take())
+        };
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = next.clone(),
+            None => self.head = next.clone(),
+        }
+        match next {
+            Some(next) => next.borrow_mut().prev = prev,
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+    }
+
+    /// Unlinks `node` from the list and returns its element.
+    fn unlink(&mut self, node: Rc<RefCell<Node<T>>>) -> T {
+        self.detach(&node);
+        Rc::try_unwrap(node)
+            .ok()
+            .expect("only the list holds strong references to its nodes")
+            .into_inner()
+            .element
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut list = f.debug_list();
+        self.for_each(|x| {
+            list.entry(x);
+        });
+        list.finish()
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for x in iter {
+            list.push_back(x);
+        }
+        list
+    }
+}
+
+/// A handle to an element of a `LinkedList`, returned by `push_front_handle`
+/// and `push_back_handle`.
+///
+/// The handle holds a weak reference, so it doesn't keep the element alive
+/// and goes stale once the element is removed. The node's allocation is only
+/// freed once its handles are dropped too.
+pub struct NodeHandle<T>(Weak<RefCell<Node<T>>>);
+
+impl<T> NodeHandle<T> {
+    /// Returns `true` if the element is still in a list.
+    pub fn is_live(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+}
+
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// A cursor over a `LinkedList`.
+///
+/// A cursor points at an element, or at a "ghost" non-element between the
+/// back and the front of the list. Moving past either end lands on the ghost,
+/// and moving on from the ghost wraps around to the other end.
+pub struct Cursor<'a, T> {
+    // Weak, so the cursor never keeps a node from being unwrapped once the
+    // list is edited again.
+    current: Option<Weak<RefCell<Node<T>>>>,
+    index: usize,
+    list: &'a LinkedList<T>,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Returns the index of the current element, or `None` at the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    /// Moves to the next element.
+    pub fn move_next(&mut self) {
+        match self
+            .node()
+            .map(|node| node.borrow().next.as_ref().map(Rc::downgrade))
+        {
+            Some(next) => {
+                self.current = next;
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head.as_ref().map(Rc::downgrade);
+                self.index = 0;
+            }
+        }
+    }
+
+    /// Moves to the previous element.
+    pub fn move_prev(&mut self) {
+        match self
+            .node()
+            .map(|node| node.borrow().prev.as_ref().map(Rc::downgrade))
+        {
+            Some(prev) => {
+                self.current = prev;
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            }
+            None => {
+                self.current = self.list.tail.as_ref().map(Rc::downgrade);
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Returns the current element, or `None` at the ghost.
+    pub fn current(&self) -> Option<Ref<'_, T>> {
+        self.node()
+            .map(|node| Ref::map(node.borrow(), |node| &node.element))
+    }
+
+    fn node(&self) -> Option<&RefCell<Node<T>>> {
+        // SAFETY: the current node is linked into `self.list`, which is
+        // borrowed for as long as the cursor lives, so it's still allocated.
+        self.current.as_ref().map(|node| unsafe { &*node.as_ptr() })
+    }
+}
+
+/// A cursor over a `LinkedList` that can also edit the list.
+pub struct CursorMut<'a, T> {
+    current: Option<Weak<RefCell<Node<T>>>>,
+    index: usize,
+    list: &'a mut LinkedList<T>,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    /// Returns the index of the current element, or `None` at the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    /// Moves to the next element.
+    pub fn move_next(&mut self) {
+        match self
+            .node()
+            .map(|node| node.borrow().next.as_ref().map(Rc::downgrade))
+        {
+            Some(next) => {
+                self.current = next;
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head.as_ref().map(Rc::downgrade);
+                self.index = 0;
+            }
+        }
+    }
+
+    /// Moves to the previous element.
+    pub fn move_prev(&mut self) {
+        match self
+            .node()
+            .map(|node| node.borrow().prev.as_ref().map(Rc::downgrade))
+        {
+            Some(prev) => {
+                self.current = prev;
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            }
+            None => {
+                self.current = self.list.tail.as_ref().map(Rc::downgrade);
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Returns the current element, or `None` at the ghost.
+    pub fn current(&mut self) -> Option<RefMut<'_, T>> {
+        self.node()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.element))
+    }
+
+    /// Inserts an element before the current one. At the ghost, the element
+    /// becomes the new back.
+    pub fn insert_before(&mut self, elt: T) {
+        let mut list = LinkedList::new();
+        list.push_back(elt);
+        self.splice_before(list);
+    }
+
+    /// Inserts an element after the current one. At the ghost, the element
+    /// becomes the new front.
+    pub fn insert_after(&mut self, elt: T) {
+        let mut list = LinkedList::new();
+        list.push_back(elt);
+        self.splice_after(list);
+    }
+
+    /// Removes the current element and moves to the next one. Returns `None`
+    /// and does nothing at the ghost.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.upgrade()?;
+        self.current = node.borrow().next.as_ref().map(Rc::downgrade);
+        Some(self.list.unlink(node))
+    }
+
+    /// Moves all elements of `other` in after the current one. At the ghost,
+    /// they're inserted at the front. This runs in *O*(1).
+    pub fn splice_after(&mut self, other: LinkedList<T>) {
+        let current = self.upgrade();
+        let next = match &current {
+            Some(node) => node.borrow().next.clone(),
+            None => self.list.head.clone(),
+        };
+        if current.is_none() {
+            self.index += other.len;
+        }
+        self.list.splice_between(current, next, other);
+    }
+
+    /// Moves all elements of `other` in before the current one. At the ghost,
+    /// they're inserted at the back. This runs in *O*(1).
+    pub fn splice_before(&mut self, other: LinkedList<T>) {
+        let current = self.upgrade();
+        let prev = match &current {
+            Some(node) => node.borrow().prev.clone(),
+            None => self.list.tail.clone(),
+        };
+        self.index += other.len;
+        self.list.splice_between(prev, current, other);
+    }
+
+    /// Splits the list after the current element and returns everything
+    /// after it. At the ghost, the whole list is returned. Like `split_off`,
+    /// this runs in *O*(min(k, n - k)) for parts of k and n - k elements.
+    pub fn split_after(&mut self) -> LinkedList<T> {
+        let len_before = match self.current {
+            Some(_) => self.index + 1,
+            None => 0,
+        };
+        let rest = self.list.split_after_node(self.upgrade(), len_before);
+        if self.current.is_none() {
+            self.index = 0;
+        }
+        rest
+    }
+
+    /// Splits the list before the current element and returns everything
+    /// before it. At the ghost, the whole list is returned. This costs the
+    /// same as `split_after`.
+    pub fn split_before(&mut self) -> LinkedList<T> {
+        let prev = match self.node() {
+            Some(node) => node.borrow().prev.clone(),
+            None => self.list.tail.clone(),
+        };
+        let mut front = self.list.split_after_node(prev, self.index);
+        mem::swap(self.list, &mut front);
+        self.index = 0;
+        front
+    }
+
+    fn node(&self) -> Option<&RefCell<Node<T>>> {
+        // SAFETY: the current node is linked into `self.list`, which is
+        // borrowed for as long as the cursor lives, so it's still allocated.
+        self.current.as_ref().map(|node| unsafe { &*node.as_ptr() })
+    }
+
+    fn upgrade(&self) -> Link<T> {
+        self.current.as_ref().and_then(Weak::upgrade)
+    }
 }
 
 pub struct IntoIter<T>(LinkedList<T>);
@@ -157,19 +788,53 @@ impl<T> Drop for LinkedList<T> {
 }
 
 impl<T> Node<T> {
-    pub fn new(value: T) -> Rc<RefCell<Self>> {
+    pub fn new(value: T, owner: Rc<Owner>) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self {
             element: value,
             next: None,
             prev: None,
+            owner,
         }))
     }
 }
 
+impl Owner {
+    /// Follows the forwarding chain from `owner` to the token still in use,
+    /// pointing every token on the way straight at it.
+    fn root(owner: &Rc<Owner>) -> Rc<Owner> {
+        let mut root = owner.clone();
+        loop {
+            let next = root.forward.borrow().clone();
+            match next {
+                Some(next) => root = next,
+                None => break,
+            }
+        }
+        let mut current = owner.clone();
+        while !Rc::ptr_eq(&current, &root) {
+            let next = current.forward.replace(Some(root.clone()));
+            current = next.expect("every token before the root forwards");
+        }
+        root
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::LinkedList;
 
+    fn to_vec(list: LinkedList<i32>) -> Vec<i32> {
+        list.into_iter().collect()
+    }
+
+    fn from_range(range: std::ops::Range<i32>) -> LinkedList<i32> {
+        let mut list = LinkedList::new();
+        for i in range {
+            list.push_back(i);
+        }
+        list
+    }
+
     #[test]
     fn basic_front() {
         let mut list = LinkedList::new();
@@ -240,4 +905,224 @@ mod test {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next_back(), None);
     }
+
+    #[test]
+    fn append_split_off() {
+        let mut list = from_range(0..3);
+        let mut other = from_range(3..6);
+        list.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(list.len(), 6);
+
+        let back = list.split_off(4);
+        assert_eq!(list.len(), 4);
+        assert_eq!(to_vec(back), vec![4, 5]);
+
+        let back = list.split_off(1);
+        assert_eq!(to_vec(back), vec![1, 2, 3]);
+        assert_eq!(list.split_off(1).len(), 0);
+        assert_eq!(to_vec(list.split_off(0)), vec![0]);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn cursor() {
+        let list = from_range(0..3);
+        let mut cursor = list.cursor_front();
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(*cursor.current().unwrap(), 2);
+        cursor.move_next();
+        assert!(cursor.current().is_none());
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(2));
+
+        let empty = LinkedList::<i32>::new();
+        assert!(empty.cursor_back().current().is_none());
+    }
+
+    #[test]
+    fn cursor_mut() {
+        let mut list = from_range(0..5);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        *cursor.current().unwrap() = 10;
+        cursor.insert_before(20);
+        cursor.insert_after(30);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.remove_current(), Some(10));
+        assert_eq!(*cursor.current().unwrap(), 30);
+        assert_eq!(cursor.index(), Some(2));
+
+        // At the ghost, insertions wrap around to either end
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        cursor.insert_after(-1);
+        cursor.insert_before(5);
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(7));
+        assert_eq!(to_vec(list), vec![-1, 0, 20, 30, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn cursor_out_of_scope_use() {
+        // A cursor that's still in scope but no longer used doesn't keep its
+        // node from being popped.
+        let mut list = from_range(0..3);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        *cursor.current().unwrap() = 10;
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), Some(10));
+
+        let cursor = list.cursor_front();
+        assert_eq!(*cursor.current().unwrap(), 0);
+        assert_eq!(list.pop_front(), Some(0));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn cursor_split_splice() {
+        let mut list = from_range(0..6);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let front = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        let back = cursor.split_after();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(to_vec(front), vec![0, 1]);
+        assert_eq!(to_vec(back), vec![3, 4, 5]);
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after(from_range(10..12));
+        cursor.splice_before(from_range(20..22));
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(*cursor.current().unwrap(), 2);
+        cursor.remove_current();
+        cursor.remove_current();
+        cursor.remove_current();
+        assert!(cursor.current().is_none());
+        assert_eq!(list.len(), 2);
+
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        assert_eq!(cursor.split_before().len(), 2);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn traversal() {
+        let mut list: LinkedList<_> = (1..=4).collect();
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.fold(0, |acc, x| acc * 10 + x), 1234);
+        assert_eq!(list.rfold(0, |acc, x| acc * 10 + x), 4321);
+
+        list.for_each_mut(|x| *x *= 2);
+        let mut seen = Vec::new();
+        list.rfor_each(|x| seen.push(*x));
+        assert_eq!(seen, vec![8, 6, 4, 2]);
+        assert_eq!(format!("{:?}", list), "[2, 4, 6, 8]");
+
+        assert!(list.contains(&6));
+        assert!(!list.contains(&3));
+        list.clear();
+        assert!(list.is_empty());
+        assert!(!list.contains(&6));
+        assert_eq!(format!("{:?}", list), "[]");
+    }
+
+    #[test]
+    fn handles() {
+        let mut list = LinkedList::new();
+        let one = list.push_back_handle(1);
+        let two = list.push_back_handle(2);
+        let three = list.push_back_handle(3);
+        let zero = list.push_front_handle(0);
+
+        assert_eq!(list.remove(&two), Some(2));
+        assert!(!two.is_live());
+        assert_eq!(list.remove(&two), None);
+        assert!(!list.move_to_front(&two));
+
+        assert!(list.move_to_front(&three));
+        assert!(list.move_to_back(&zero));
+        assert!(list.move_to_back(&zero));
+        assert_eq!(
+            list.fold(Vec::new(), |mut v, x| {
+                v.push(*x);
+                v
+            }),
+            vec![3, 1, 0]
+        );
+
+        // Popping invalidates handles too
+        assert_eq!(list.pop_front(), Some(3));
+        assert!(!three.is_live());
+        assert_eq!(list.remove(&zero.clone()), Some(0));
+        assert_eq!(list.remove(&one), Some(1));
+        assert!(list.is_empty());
+        assert!(list.front().is_none() && list.back().is_none());
+
+        // Handles follow their element into another list
+        let mut list: LinkedList<_> = (0..4).collect();
+        let four = list.push_back_handle(4);
+        let mut back = list.split_off(2);
+        assert_eq!(back.remove(&four), Some(4));
+        assert_eq!(back.len(), 2);
+    }
+
+    #[test]
+    fn foreign_handles() {
+        let mut list: LinkedList<_> = (0..3).collect();
+        let three = list.push_back_handle(3);
+        let mut other = LinkedList::new();
+        assert_eq!(other.remove(&three), None);
+        assert!(!other.move_to_front(&three));
+        assert!(other.is_empty());
+
+        other.push_back(10);
+        assert_eq!(other.remove(&three), None);
+        assert!(!other.move_to_back(&three));
+        assert_eq!(other.len(), 1);
+        assert_eq!(list.len(), 4);
+
+        // Whichever side gets relabeled, handles move along with their
+        // element and stop matching the list they left.
+        let mut tail = list.split_off(1);
+        assert_eq!(list.remove(&three), None);
+        assert!(tail.move_to_front(&three));
+        other.append(&mut tail);
+        assert_eq!(tail.remove(&three), None);
+        assert_eq!(other.remove(&three), Some(3));
+        assert_eq!(to_vec(other), vec![10, 1, 2]);
+
+        let mut list: LinkedList<_> = (0..5).collect();
+        let minus_one = list.push_front_handle(-1);
+        let mut short: LinkedList<_> = (10..11).collect();
+        short.append(&mut list);
+        assert!(short.move_to_back(&minus_one));
+        let back = short.split_off(6);
+        assert_eq!(short.remove(&minus_one), None);
+        assert_eq!(short.len(), 6);
+        assert_eq!(to_vec(back), vec![-1]);
+
+        // Appending a longer list forwards the shorter list's token, so a
+        // handle can sit behind a chain of them.
+        let mut list = LinkedList::new();
+        let zero = list.push_back_handle(0);
+        list.append(&mut (1..4).collect());
+        list.append(&mut (4..20).collect());
+        assert!(list.move_to_back(&zero));
+        let mut rest = list.split_off(1);
+        assert_eq!(list.remove(&zero), None);
+        assert_eq!(rest.remove(&zero), Some(0));
+        assert_eq!(rest.len(), 18);
+    }
 }
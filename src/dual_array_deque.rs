@@ -0,0 +1,370 @@
+#![allow(unused)]
+//! DualArrayDeque: a list built from two ArrayStacks placed back to back, as
+//! described in
+//! [Open Data Structures](https://opendatastructures.org/ods-java/2_5_DualArrayDeque_Building.html).
+//!
+//! `front` holds the first elements in reverse order, so both ends of the list
+//! are at the ends of a stack. `get`/`set` run in *O*(1) and `add(i)`/
+//! `remove(i)` in *O*(1 + min(i, n - i)) amortized. Whenever one stack grows
+//! to more than three times the size of the other, the elements are split
+//! evenly between the two again.
+use crate::array_stack::Stack;
+use crate::rootish_array_stack::RootishStack;
+use std::ops::{Index, IndexMut};
+use std::{iter, mem, slice};
+
+pub struct DualArrayDeque<T> {
+    halves: Halves<Stack<T>>,
+}
+
+impl<T> DualArrayDeque<T> {
+    pub fn new() -> Self {
+        Self {
+            halves: Halves::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.halves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.halves.get(i)
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.halves.get_mut(i)
+    }
+
+    /// Replaces the element at index `i` and returns the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) -> T {
+        mem::replace(&mut self[i], value)
+    }
+
+    /// Inserts an element at index `i`, shifting the elements on the shorter
+    /// side of `i` to make room.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is greater than the deque's length.
+    pub fn add(&mut self, i: usize, value: T) {
+        self.halves.add(i, value)
+    }
+
+    /// Removes and returns the element at index `i`, or returns `None` if `i`
+    /// is out of bounds.
+    pub fn remove(&mut self, i: usize) -> Option<T> {
+        self.halves.remove(i)
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.add(0, value)
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.add(self.len(), value)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.len().checked_sub(1).and_then(|i| self.remove(i))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.halves
+            .front
+            .iter()
+            .rev()
+            .chain(self.halves.back.iter())
+    }
+}
+
+/// The stack operations a half of a dual deque needs, so the same [`Halves`]
+/// code serves both [`Stack`] and [`RootishStack`].
+pub(crate) trait ListStack {
+    type Item;
+
+    fn new() -> Self;
+    fn len(&self) -> usize;
+    fn get(&self, i: usize) -> Option<&Self::Item>;
+    fn get_mut(&mut self, i: usize) -> Option<&mut Self::Item>;
+    fn add(&mut self, i: usize, value: Self::Item);
+    fn remove(&mut self, i: usize) -> Option<Self::Item>;
+    fn push(&mut self, value: Self::Item);
+    fn pop(&mut self) -> Option<Self::Item>;
+    fn swap(&mut self, i: usize, j: usize);
+
+    /// Rotates the stack in place so that the element at index `k` ends up
+    /// at the bottom.
+    fn rotate_left(&mut self, k: usize) {
+        let len = self.len();
+        self.reverse(0, k);
+        self.reverse(k, len);
+        self.reverse(0, len);
+    }
+
+    /// Reverses the elements in `start..end` in place.
+    fn reverse(&mut self, mut start: usize, mut end: usize) {
+        while start + 1 < end {
+            end -= 1;
+            self.swap(start, end);
+            start += 1;
+        }
+    }
+}
+
+impl<T> ListStack for Stack<T> {
+    type Item = T;
+
+    fn new() -> Self {
+        Stack::new()
+    }
+
+    fn len(&self) -> usize {
+        Stack::len(self)
+    }
+
+    fn get(&self, i: usize) -> Option<&T> {
+        Stack::get(self, i)
+    }
+
+    fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        Stack::get_mut(self, i)
+    }
+
+    fn add(&mut self, i: usize, value: T) {
+        Stack::add(self, i, value)
+    }
+
+    fn remove(&mut self, i: usize) -> Option<T> {
+        Stack::remove(self, i)
+    }
+
+    fn push(&mut self, value: T) {
+        Stack::push(self, value)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Stack::pop(self)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.as_mut_slice().swap(i, j)
+    }
+}
+
+impl<T> ListStack for RootishStack<T> {
+    type Item = T;
+
+    fn new() -> Self {
+        RootishStack::new()
+    }
+
+    fn len(&self) -> usize {
+        RootishStack::len(self)
+    }
+
+    fn get(&self, i: usize) -> Option<&T> {
+        RootishStack::get(self, i)
+    }
+
+    fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        RootishStack::get_mut(self, i)
+    }
+
+    fn add(&mut self, i: usize, value: T) {
+        RootishStack::add(self, i, value)
+    }
+
+    fn remove(&mut self, i: usize) -> Option<T> {
+        RootishStack::remove(self, i)
+    }
+
+    fn push(&mut self, value: T) {
+        RootishStack::push(self, value)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        RootishStack::pop(self)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        RootishStack::swap(self, i, j)
+    }
+}
+
+/// Two stacks placed back to back, shared by [`DualArrayDeque`] and
+/// [`RootishArrayDeque`](crate::rootish_array_deque::RootishArrayDeque).
+pub(crate) struct Halves<S> {
+    // First elements in reverse order.
+    pub(crate) front: S,
+    pub(crate) back: S,
+}
+
+impl<S: ListStack> Halves<S> {
+    pub(crate) fn new() -> Self {
+        Self {
+            front: S::new(),
+            back: S::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub(crate) fn get(&self, i: usize) -> Option<&S::Item> {
+        if i < self.front.len() {
+            self.front.get(self.front.len() - i - 1)
+        } else {
+            self.back.get(i - self.front.len())
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, i: usize) -> Option<&mut S::Item> {
+        if i < self.front.len() {
+            let i = self.front.len() - i - 1;
+            self.front.get_mut(i)
+        } else {
+            self.back.get_mut(i - self.front.len())
+        }
+    }
+
+    pub(crate) fn add(&mut self, i: usize, value: S::Item) {
+        assert!(i <= self.len(), "index out of bounds");
+        if i < self.front.len() {
+            self.front.add(self.front.len() - i, value);
+        } else {
+            self.back.add(i - self.front.len(), value);
+        }
+        self.balance();
+    }
+
+    pub(crate) fn remove(&mut self, i: usize) -> Option<S::Item> {
+        let value = if i < self.front.len() {
+            self.front.remove(self.front.len() - i - 1)
+        } else {
+            self.back.remove(i - self.front.len())
+        };
+        self.balance();
+        value
+    }
+
+    /// Splits the elements evenly between the two stacks if one of them has
+    /// more than three times as many as the other. Elements are only moved
+    /// between the two stacks and swapped within them, so no extra space is
+    /// needed.
+    fn balance(&mut self) {
+        let (front, back) = (self.front.len(), self.back.len());
+        if 3 * front >= back && 3 * back >= front {
+            return;
+        }
+
+        // Moving `front` onto `back` leaves the list rotated left by `front`.
+        // Rotate it so the first `half` elements are on top, then move those
+        // back to `front`.
+        while let Some(value) = self.front.pop() {
+            self.back.push(value);
+        }
+        let len = front + back;
+        let half = len / 2;
+        self.back.rotate_left((half + len - front) % len);
+        for _ in 0..half {
+            let value = self.back.pop().expect("back holds every element");
+            self.front.push(value);
+        }
+    }
+}
+
+pub type Iter<'a, T> = iter::Chain<iter::Rev<slice::Iter<'a, T>>, slice::Iter<'a, T>>;
+
+impl<T> Index<usize> for DualArrayDeque<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for DualArrayDeque<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        self.get_mut(i).expect("index out of bounds")
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DualArrayDeque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DualArrayDeque;
+
+    fn to_vec(deque: &DualArrayDeque<u32>) -> Vec<u32> {
+        deque.iter().copied().collect()
+    }
+
+    #[test]
+    fn basic() {
+        let mut deque = DualArrayDeque::new();
+        deque.push_back(2);
+        deque.push_front(0);
+        deque.add(1, 1);
+        deque.push_back(3);
+        assert_eq!(to_vec(&deque), vec![0, 1, 2, 3]);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(deque[3], 3);
+
+        assert_eq!(deque.set(1, 10), 1);
+        assert_eq!(deque.remove(2), Some(2));
+        assert_eq!(deque.remove(3), None);
+        assert_eq!(to_vec(&deque), vec![0, 10, 3]);
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_back(), Some(10));
+        assert_eq!(deque.pop_back(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn balance() {
+        let mut deque = DualArrayDeque::new();
+        for i in 0..100 {
+            deque.push_back(i);
+        }
+        assert!(deque.halves.front.len() * 3 >= deque.halves.back.len());
+        assert!(deque.halves.back.len() * 3 >= deque.halves.front.len());
+
+        for _ in 0..90 {
+            deque.pop_front();
+        }
+        assert_eq!(to_vec(&deque), (90..100).collect::<Vec<_>>());
+        assert!(deque.halves.front.len() * 3 >= deque.halves.back.len());
+        assert!(deque.halves.back.len() * 3 >= deque.halves.front.len());
+
+        for i in 0..100 {
+            deque.push_front(i);
+        }
+        let expected: Vec<_> = (0..100).rev().chain(90..100).collect();
+        assert_eq!(to_vec(&deque), expected);
+        assert!(deque.halves.front.len() * 3 >= deque.halves.back.len());
+        assert!(deque.halves.back.len() * 3 >= deque.halves.front.len());
+    }
+}
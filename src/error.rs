@@ -0,0 +1,79 @@
+#![allow(unused)]
+use std::fmt;
+
+/// The error type for `try_reserve`-style methods of the array-backed
+/// containers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The computed capacity exceeded `usize::MAX`.
+    CapacityOverflow,
+    /// The container would have to grow past the limit it was constructed
+    /// with.
+    CapacityExceeded { max_capacity: usize },
+    /// The allocator reported a failure.
+    AllocError,
+}
+
+impl From<std::collections::TryReserveError> for TryReserveError {
+    fn from(_: std::collections::TryReserveError) -> Self {
+        TryReserveError::AllocError
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => write!(f, "capacity overflow"),
+            TryReserveError::CapacityExceeded { max_capacity } => {
+                write!(f, "capacity limit of {} exceeded", max_capacity)
+            }
+            TryReserveError::AllocError => write!(f, "memory allocation failed"),
+        }
+    }
+}
+
+impl std::error::Error for TryReserveError {}
+
+/// The error type for `try_push`-style methods of the array-backed
+/// containers. Hands back the value that couldn't be added along with the
+/// reason.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TryPushError<T> {
+    value: T,
+    error: TryReserveError,
+}
+
+impl<T> TryPushError<T> {
+    pub(crate) fn new(value: T, error: TryReserveError) -> Self {
+        Self { value, error }
+    }
+
+    /// Returns the reason the value couldn't be added.
+    pub fn error(&self) -> &TryReserveError {
+        &self.error
+    }
+
+    /// Returns the value that couldn't be added.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Splits the error into the value and the reason.
+    pub fn into_parts(self) -> (T, TryReserveError) {
+        (self.value, self.error)
+    }
+}
+
+impl<T> From<TryPushError<T>> for TryReserveError {
+    fn from(err: TryPushError<T>) -> Self {
+        err.error
+    }
+}
+
+impl<T> fmt::Display for TryPushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for TryPushError<T> {}
@@ -0,0 +1,271 @@
+#![allow(unused)]
+//! Generational arena: a slab whose handles detect use-after-remove.
+//!
+//! Values live in a [`Slab`] together with the generation they were inserted
+//! in, taken from a counter that every insert bumps. A handle remembers its
+//! value's generation, so a stale handle to a reused slot simply finds
+//! nothing instead of aliasing the new value.
+use crate::slab::{self, Slab};
+use std::ops;
+
+/// Handle to a value in a [`GenArena`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Index {
+    slot: usize,
+    generation: u64,
+}
+
+impl Index {
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+pub struct GenArena<T> {
+    slab: Slab<(u64, T)>,
+    // Generation of the next insert.
+    generation: u64,
+}
+
+impl<T> GenArena<T> {
+    pub fn new() -> Self {
+        GenArena::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slab: Slab::with_capacity(capacity),
+            generation: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Stores a value and returns a handle to it.
+    pub fn insert(&mut self, value: T) -> Index {
+        let generation = self.generation;
+        self.generation += 1;
+        let slot = self.slab.insert((generation, value));
+        Index { slot, generation }
+    }
+
+    /// Removes the value behind `index` and returns it, or `None` if it was
+    /// already removed.
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        self.get(index)?;
+        self.slab.remove(index.slot).map(|(_, value)| value)
+    }
+
+    pub fn get(&self, index: Index) -> Option<&T> {
+        match self.slab.get(index.slot) {
+            Some((generation, value)) if *generation == index.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        Self::checked(self.slab.get_mut(index.slot), index)
+    }
+
+    /// Returns mutable references to two values at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both handles point to the same slot.
+    pub fn get2_mut(&mut self, a: Index, b: Index) -> (Option<&mut T>, Option<&mut T>) {
+        let (x, y) = self.slab.get2_mut(a.slot, b.slot);
+        (Self::checked(x, a), Self::checked(y, b))
+    }
+
+    pub fn contains(&self, index: Index) -> bool {
+        self.get(index).is_some()
+    }
+
+    /// Keeps only the values for which `f` returns `true`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Index, &mut T) -> bool,
+    {
+        self.slab.retain(|slot, (generation, value)| {
+            let generation = *generation;
+            f(Index { slot, generation }, value)
+        });
+    }
+
+    /// Removes all values, invalidating every handle.
+    pub fn clear(&mut self) {
+        self.slab.clear();
+    }
+
+    /// Iterates over values as `(handle, &value)`, in slot order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.slab.iter())
+    }
+
+    /// Iterates over values as `(handle, &mut value)`, in slot order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut(self.slab.iter_mut())
+    }
+
+    fn checked(entry: Option<&mut (u64, T)>, index: Index) -> Option<&mut T> {
+        match entry {
+            Some((generation, value)) if *generation == index.generation => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T> ops::Index<Index> for GenArena<T> {
+    type Output = T;
+
+    fn index(&self, index: Index) -> &T {
+        self.get(index).expect("stale or invalid handle")
+    }
+}
+
+impl<T> ops::IndexMut<Index> for GenArena<T> {
+    fn index_mut(&mut self, index: Index) -> &mut T {
+        self.get_mut(index).expect("stale or invalid handle")
+    }
+}
+
+pub struct Iter<'a, T>(slab::Iter<'a, (u64, T)>);
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Index, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (slot, (generation, value)) = self.0.next()?;
+        let generation = *generation;
+        Some((Index { slot, generation }, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+pub struct IterMut<'a, T>(slab::IterMut<'a, (u64, T)>);
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (Index, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (slot, (generation, value)) = self.0.next()?;
+        let generation = *generation;
+        Some((Index { slot, generation }, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a GenArena<T> {
+    type Item = (Index, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut GenArena<T> {
+    type Item = (Index, &'a mut T);
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GenArena;
+
+    #[test]
+    fn basic() {
+        let mut arena = GenArena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena[a], "a");
+        assert_eq!(arena.get(b), Some(&"b"));
+
+        assert_eq!(arena.remove(a), Some("a"));
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.get(a), None);
+
+        // Slot is reused, but the old handle stays dead
+        let c = arena.insert("c");
+        assert_eq!(c.slot(), a.slot());
+        assert_ne!(c.generation(), a.generation());
+        assert_eq!(arena.get(a), None);
+        assert!(!arena.contains(a));
+        assert_eq!(arena[c], "c");
+
+        arena[c] = "C";
+        *arena.get_mut(b).unwrap() = "B";
+        assert_eq!(arena.iter().collect::<Vec<_>>(), vec![(c, &"C"), (b, &"B")]);
+    }
+
+    #[test]
+    fn get2_mut() {
+        let mut arena = GenArena::new();
+        let a = arena.insert(1);
+        let b = arena.insert(2);
+
+        let (x, y) = arena.get2_mut(b, a);
+        std::mem::swap(x.unwrap(), y.unwrap());
+        assert_eq!((arena[a], arena[b]), (2, 1));
+
+        arena.remove(a);
+        let (x, y) = arena.get2_mut(a, b);
+        assert!(x.is_none());
+        assert_eq!(y, Some(&mut 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn get2_mut_same_slot() {
+        let mut arena = GenArena::new();
+        let a = arena.insert(1);
+        arena.get2_mut(a, a);
+    }
+
+    #[test]
+    fn retain() {
+        let mut arena = GenArena::new();
+        let handles: Vec<_> = (0..6).map(|i| arena.insert(i)).collect();
+        arena.retain(|_, value| *value % 2 == 0);
+        assert_eq!(arena.len(), 3);
+        assert!(arena.contains(handles[2]));
+        assert!(!arena.contains(handles[3]));
+
+        for (_, value) in &mut arena {
+            *value *= 10;
+        }
+        let values: Vec<_> = arena.iter().map(|(_, v)| *v).collect();
+        assert_eq!(values, vec![0, 20, 40]);
+
+        arena.clear();
+        assert!(arena.is_empty());
+        assert!(!arena.contains(handles[0]));
+
+        // Slots start over from 0, generations don't
+        let d = arena.insert(7);
+        assert_eq!(d.slot(), handles[0].slot());
+        assert!(!arena.contains(handles[0]));
+        assert_eq!(arena[d], 7);
+    }
+}
@@ -1,4 +1,5 @@
 #![allow(unused)]
+use crate::error::{TryPushError, TryReserveError};
 use core::panic;
 use std::hash::{Hash, Hasher};
 
@@ -10,6 +11,7 @@ where
 {
     buckets: Vec<Option<(K, V)>>,
     len: usize,
+    max_capacity: usize,
 }
 
 impl<K, V> HashMap<K, V>
@@ -21,14 +23,29 @@ where
         Self {
             buckets: Vec::new(),
             len: 0,
+            max_capacity: usize::MAX,
         }
     }
 
+    /// Creates an empty `HashMap` whose bucket array never grows past
+    /// `max_capacity`. Once the map is at its load limit, inserting a new key
+    /// fails with [`TryReserveError::CapacityExceeded`] (`try_insert`,
+    /// `try_entry`) or panics (`insert`, `entry`).
+    pub fn with_max_capacity(max_capacity: usize) -> Self {
+        let mut map = HashMap::new();
+        map.max_capacity = max_capacity;
+        map
+    }
+
     /// Creates an empty `HashMap` with space for at least `capacity` elements.
     pub fn with_capacity(capacity: usize) -> Self {
         let mut buckets = Vec::new();
         buckets.resize_with(capacity, Default::default);
-        Self { buckets, len: 0 }
+        Self {
+            buckets,
+            len: 0,
+            max_capacity: usize::MAX,
+        }
     }
 
     /// Returns the number of elements in the map.
@@ -48,20 +65,48 @@ where
     /// If the map did have this key present, the value is updated, and the old
     /// value is returned.
     pub fn insert(&mut self, k: K, v: V) -> Option<V> {
-        // Grow underlying Vec if length is at 60% of capacity.
-        let threshold = self.buckets.len() * 6 / 10;
-        if self.buckets.is_empty() || self.len() >= threshold {
-            self.grow()
+        self.try_insert(k, v)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`HashMap::insert`], but returns an error instead of panicking if
+    /// the map needs to grow and can't. Existing keys can always be
+    /// overwritten. The error gives the key and value back.
+    pub fn try_insert(&mut self, k: K, v: V) -> Result<Option<V>, TryPushError<(K, V)>> {
+        if let Err(err) = self.try_make_room(&k) {
+            return Err(TryPushError::new((k, v), err));
         }
 
-        let index = self.find_or_find_insert_slot(&k)?;
-        let old = std::mem::replace(&mut self.buckets[index], Some((k, v)));
+        let index = self
+            .find_or_find_insert_slot(&k)
+            .expect("we allocated at least DEFAULT_CAPACITY, can't be empty");
+        let old = self.buckets[index].replace((k, v));
         if let Some(elt) = old {
-            Some(elt.1)
+            Ok(Some(elt.1))
         } else {
             self.len += 1;
-            None
+            Ok(None)
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements without
+    /// passing the load limit, so they can be inserted without growing.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        let mut size = std::cmp::max(self.buckets.len(), DEFAULT_CAPACITY);
+        while size * 6 / 10 < required {
+            size = size
+                .checked_mul(2)
+                .ok_or(TryReserveError::CapacityOverflow)?;
+        }
+        if size > self.buckets.len() {
+            self.try_resize(size)?;
         }
+        Ok(())
     }
 
     /// Returns the value corresponding to the supplied key.
@@ -85,7 +130,7 @@ where
 
     /// An iterator visiting all key-value pairs in arbitrary order.
     /// The iterator element type is `(&'a K, &'a V)`.
-    pub fn iter(&self) -> Iter<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
         Iter {
             index: 0,
             buckets: &self.buckets,
@@ -113,44 +158,87 @@ where
     }
 
     /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is new and the map can't grow to make room for it,
+    /// see [`HashMap::try_entry`].
     pub fn entry<'a>(&'a mut self, k: K) -> Entry<'a, K, V> {
-        // Grow underlying Vec if length is at 60% of capacity.
-        let threshold = self.buckets.len() * 6 / 10;
-        if self.buckets.is_empty() || self.len() >= threshold {
-            self.grow()
+        self.try_entry(k).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`HashMap::entry`], but returns an error instead of panicking if
+    /// the map needs to grow and can't. Entries of existing keys are always
+    /// available. The error gives the key back.
+    pub fn try_entry<'a>(&'a mut self, k: K) -> Result<Entry<'a, K, V>, TryPushError<K>> {
+        if let Err(err) = self.try_make_room(&k) {
+            return Err(TryPushError::new(k, err));
         }
 
         let index = self.find_or_find_insert_slot(&k).unwrap();
         match self.buckets[index] {
-            Some(ref mut elt) => Entry::Occupied(OccupiedEntry { index, map: self }),
-            None => Entry::Vacant(VacantEntry {
+            Some(ref mut elt) => Ok(Entry::Occupied(OccupiedEntry { index, map: self })),
+            None => Ok(Entry::Vacant(VacantEntry {
                 key: k,
                 index,
                 map: self,
-            }),
+            })),
         }
     }
 
-    fn grow(&mut self) {
+    // Grows the map if it's at its load limit. Failing to grow is only an
+    // error if `k` would need a new slot.
+    fn try_make_room(&mut self, k: &K) -> Result<(), TryReserveError> {
+        if self.needs_grow() {
+            if let Err(err) = self.try_grow() {
+                match self.find_or_find_insert_slot(k) {
+                    Some(index) if self.buckets[index].is_some() => (),
+                    _ => return Err(err),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Grow underlying Vec if length is at 60% of capacity.
+    fn needs_grow(&self) -> bool {
+        let threshold = self.buckets.len() * 6 / 10;
+        self.buckets.is_empty() || self.len() >= threshold
+    }
+
+    fn try_grow(&mut self) -> Result<(), TryReserveError> {
         let size = match self.buckets.len() {
             0 => DEFAULT_CAPACITY,
-            n => self.buckets.len() * 2,
+            n => self.buckets.len().saturating_mul(2),
         };
+        let size = std::cmp::min(
+            size,
+            std::cmp::max(self.buckets.len() + 1, self.max_capacity),
+        );
+        self.try_resize(size)
+    }
+
+    fn try_resize(&mut self, size: usize) -> Result<(), TryReserveError> {
+        if size > self.max_capacity {
+            return Err(TryReserveError::CapacityExceeded {
+                max_capacity: self.max_capacity,
+            });
+        }
 
         let mut new_buckets = Vec::new();
+        new_buckets.try_reserve_exact(size)?;
         new_buckets.resize_with(size, Default::default);
 
         let old_buckets = std::mem::replace(&mut self.buckets, new_buckets);
 
-        for elt in old_buckets {
-            if let Some(elt) = elt {
-                let (k, v) = elt;
-                let index = self
-                    .find_or_find_insert_slot(&k)
-                    .expect("we allocated at least DEFAULT_CAPACITY, can't be empty");
-                let old = std::mem::replace(&mut self.buckets[index], Some((k, v)));
-            }
+        for elt in old_buckets.into_iter().flatten() {
+            let (k, v) = elt;
+            let index = self
+                .find_or_find_insert_slot(&k)
+                .expect("we allocated at least DEFAULT_CAPACITY, can't be empty");
+            self.buckets[index] = Some((k, v));
         }
+        Ok(())
     }
 
     fn hash(&self, k: &K) -> u64 {
@@ -369,6 +457,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn string() {
         let mut hash_map = HashMap::new();
 
@@ -430,4 +519,32 @@ mod test {
         entry.or_insert_with(|| 1);
         assert_eq!(hash_map.get("c"), Some(&1));
     }
+
+    #[test]
+    fn max_capacity() {
+        let mut hash_map = HashMap::with_max_capacity(5);
+        assert_eq!(hash_map.try_insert(1, 1), Ok(None));
+        assert_eq!(hash_map.capacity(), 5);
+        assert_eq!(hash_map.try_insert(2, 2), Ok(None));
+        assert_eq!(hash_map.try_insert(3, 3), Ok(None));
+
+        // At the load limit, new keys are rejected but existing ones update
+        let err = TryReserveError::CapacityExceeded { max_capacity: 5 };
+        let rejected = hash_map.try_insert(4, 4).unwrap_err();
+        assert_eq!(rejected.into_parts(), ((4, 4), err.clone()));
+        assert_eq!(hash_map.try_insert(1, 10), Ok(Some(1)));
+        assert_eq!(hash_map.len(), 3);
+
+        let rejected = hash_map.try_entry(5).err().unwrap();
+        assert_eq!(rejected.into_parts(), (5, err));
+        assert!(hash_map.try_entry(2).is_ok());
+
+        let mut hash_map: HashMap<u32, u32> = HashMap::new();
+        assert_eq!(hash_map.try_reserve(12), Ok(()));
+        assert_eq!(hash_map.capacity(), 20);
+        for i in 0..12 {
+            hash_map.insert(i, i);
+        }
+        assert_eq!(hash_map.capacity(), 20);
+    }
 }
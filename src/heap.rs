@@ -16,7 +16,7 @@ where
     }
 
     fn sort(&mut self) {
-        for n in (0..self.heap.len() / 2).into_iter().rev() {
+        for n in (0..self.heap.len() / 2).rev() {
             self.bubble_down(n);
         }
     }
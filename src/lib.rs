@@ -1,19 +1,35 @@
+mod error;
+
 mod doubly_linked_deque;
 mod linked_list_simple;
 mod linked_list_tuple;
+mod persistent_queue;
+mod persistent_vector;
 mod singly_linked_stack;
+mod unsafe_doubly_linked_list;
 mod unsafe_singly_linked_queue;
 
+mod aggregate_queue;
 mod array_deque;
 mod array_queue;
 mod array_stack;
 mod auxiliary_stack;
+mod dual_array_deque;
+mod gen_arena;
+mod rootish_array_deque;
 mod rootish_array_stack;
+mod segmented_vec;
+mod slab;
 
+mod byte_buffer;
+mod channel;
 mod circular_buffer;
+mod sliding_window;
+mod spsc_buffer;
 
 mod bloom_filter;
 mod hash_map;
+mod persistent_hash_map;
 
 mod bstree_box;
 mod bstree_rc;
@@ -53,14 +53,14 @@ impl<T> List<T> {
     }
 
     /// Provides a forward iterator.
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             next: self.head.as_deref(),
         }
     }
 
     /// Provides a forward iterator with mutable references.
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             next: self.head.as_deref_mut(),
         }
@@ -169,6 +169,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::option_map_unit_fn)]
     fn peek() {
         let mut list = List::new();
         assert_eq!(list.peek(), None);
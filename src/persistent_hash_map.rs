@@ -0,0 +1,610 @@
+#![allow(unused)]
+//! Persistent hash map: a hash array mapped trie (HAMT).
+//!
+//! Each level of the trie consumes 5 bits of a key's 64-bit hash. Branches
+//! store a 32-bit bitmap of which of their 32 possible children exist and a
+//! dense array of just those, so a child's position is the number of set
+//! bits below its own. Keys whose hashes are equal in all 64 bits end up in
+//! a collision node at the bottom.
+//!
+//! Nodes and entries are shared through `Arc`. `insert` and `remove` copy
+//! only the path to the changed entry, and `diff` skips every subtree the two
+//! versions still share.
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::slice;
+use std::sync::Arc;
+
+const BITS: u32 = 5;
+const MASK: u64 = (1 << BITS) - 1;
+
+struct Leaf<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+enum Child<K, V> {
+    Leaf(Arc<Leaf<K, V>>),
+    Node(Arc<Node<K, V>>),
+}
+
+enum Node<K, V> {
+    Branch {
+        bitmap: u32,
+        children: Vec<Child<K, V>>,
+    },
+    // Only found below the last level, so all its leaves have the same hash.
+    Collision(Vec<Arc<Leaf<K, V>>>),
+}
+
+// Deriving would require `K: Clone, V: Clone`.
+impl<K, V> Clone for Child<K, V> {
+    fn clone(&self) -> Self {
+        match self {
+            Child::Leaf(leaf) => Child::Leaf(leaf.clone()),
+            Child::Node(node) => Child::Node(node.clone()),
+        }
+    }
+}
+
+/// A difference between two versions of a map, as returned by
+/// [`PersistentHashMap::diff`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Diff<'a, K, V> {
+    /// The key is only in the second map.
+    Added(&'a K, &'a V),
+    /// The key is only in the first map.
+    Removed(&'a K, &'a V),
+    /// The key is in both maps, with the first and second value.
+    Changed(&'a K, &'a V, &'a V),
+}
+
+pub struct PersistentHashMap<K, V> {
+    // Always a branch.
+    root: Arc<Node<K, V>>,
+    len: usize,
+}
+
+impl<K, V> PersistentHashMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            root: Arc::new(Node::Branch {
+                bitmap: 0,
+                children: Vec::new(),
+            }),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the entries in hash order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let children = match &*self.root {
+            Node::Branch { children, .. } => children.iter(),
+            Node::Collision(_) => unreachable!("root is always a branch"),
+        };
+        Iter {
+            stack: vec![children],
+            collision: [].iter(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K, V> PersistentHashMap<K, V>
+where
+    K: Hash + Eq,
+{
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let hash = hash(key);
+        let mut node = &*self.root;
+        let mut shift = 0;
+        loop {
+            match node {
+                Node::Branch { bitmap, children } => {
+                    let bit = bit(hash, shift);
+                    if bitmap & bit == 0 {
+                        return None;
+                    }
+                    match &children[index(*bitmap, bit)] {
+                        Child::Leaf(leaf) => {
+                            return (leaf.key == *key).then_some(&leaf.value);
+                        }
+                        Child::Node(child) => {
+                            node = child;
+                            shift += BITS;
+                        }
+                    }
+                }
+                Node::Collision(leaves) => {
+                    return leaves
+                        .iter()
+                        .find(|leaf| leaf.key == *key)
+                        .map(|leaf| &leaf.value);
+                }
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns a new map with `key` mapped to `value`, replacing the old value
+    /// if the key was present.
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let leaf = Arc::new(Leaf {
+            hash: hash(&key),
+            key,
+            value,
+        });
+        let (root, added) = self.root.insert(0, leaf);
+        Self {
+            root: Arc::new(root),
+            len: self.len + added as usize,
+        }
+    }
+
+    /// Returns a new map without `key`. If the key isn't present, the new map
+    /// shares everything with this one.
+    pub fn remove(&self, key: &K) -> Self {
+        match self.root.remove(0, hash(key), key) {
+            None => self.clone(),
+            Some(Some(Child::Node(root))) => Self {
+                root,
+                len: self.len - 1,
+            },
+            Some(None) => Self::new(),
+            Some(Some(Child::Leaf(_))) => unreachable!("root is never collapsed"),
+        }
+    }
+
+    /// Returns the differences from `self` to `other`, in no particular order.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Vec<Diff<'a, K, V>>
+    where
+        V: PartialEq,
+    {
+        let mut diffs = Vec::new();
+        if !Arc::ptr_eq(&self.root, &other.root) {
+            self.root.diff(&other.root, &mut diffs);
+        }
+        diffs
+    }
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Eq,
+{
+    /// Returns a copy of this node with `leaf` inserted, and whether the key
+    /// was new.
+    fn insert(&self, shift: u32, leaf: Arc<Leaf<K, V>>) -> (Self, bool) {
+        match self {
+            Node::Branch { bitmap, children } => {
+                let bit = bit(leaf.hash, shift);
+                let index = index(*bitmap, bit);
+                let mut children = children.clone();
+                if bitmap & bit == 0 {
+                    children.insert(index, Child::Leaf(leaf));
+                    let bitmap = bitmap | bit;
+                    return (Node::Branch { bitmap, children }, true);
+                }
+
+                let (child, added) = match &children[index] {
+                    Child::Leaf(old) if old.key == leaf.key => (Child::Leaf(leaf), false),
+                    Child::Leaf(old) => {
+                        let node = Node::merge(old.clone(), leaf, shift + BITS);
+                        (Child::Node(Arc::new(node)), true)
+                    }
+                    Child::Node(node) => {
+                        let (node, added) = node.insert(shift + BITS, leaf);
+                        (Child::Node(Arc::new(node)), added)
+                    }
+                };
+                children[index] = child;
+                let bitmap = *bitmap;
+                (Node::Branch { bitmap, children }, added)
+            }
+            Node::Collision(leaves) => {
+                let mut leaves = leaves.clone();
+                match leaves.iter().position(|old| old.key == leaf.key) {
+                    Some(i) => {
+                        leaves[i] = leaf;
+                        (Node::Collision(leaves), false)
+                    }
+                    None => {
+                        leaves.push(leaf);
+                        (Node::Collision(leaves), true)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Builds the smallest subtree at `shift` holding two leaves with
+    /// different keys.
+    fn merge(a: Arc<Leaf<K, V>>, b: Arc<Leaf<K, V>>, shift: u32) -> Self {
+        if shift >= u64::BITS {
+            return Node::Collision(vec![a, b]);
+        }
+
+        let (bit_a, bit_b) = (bit(a.hash, shift), bit(b.hash, shift));
+        if bit_a == bit_b {
+            let child = Node::merge(a, b, shift + BITS);
+            Node::Branch {
+                bitmap: bit_a,
+                children: vec![Child::Node(Arc::new(child))],
+            }
+        } else {
+            let (a, b) = if bit_a < bit_b { (a, b) } else { (b, a) };
+            Node::Branch {
+                bitmap: bit_a | bit_b,
+                children: vec![Child::Leaf(a), Child::Leaf(b)],
+            }
+        }
+    }
+
+    /// Returns `None` if `key` isn't below this node. Otherwise returns what
+    /// should replace this node in its parent: nothing if it became empty, or
+    /// its only leaf so the parent can store it directly.
+    fn remove(&self, shift: u32, hash: u64, key: &K) -> Option<Option<Child<K, V>>> {
+        match self {
+            Node::Branch { bitmap, children } => {
+                let bit = bit(hash, shift);
+                if bitmap & bit == 0 {
+                    return None;
+                }
+                let index = index(*bitmap, bit);
+                let replacement = match &children[index] {
+                    Child::Leaf(leaf) if leaf.key == *key => None,
+                    Child::Leaf(_) => return None,
+                    Child::Node(node) => node.remove(shift + BITS, hash, key)?,
+                };
+
+                let mut children = children.clone();
+                let mut bitmap = *bitmap;
+                match replacement {
+                    Some(child) => children[index] = child,
+                    None => {
+                        children.remove(index);
+                        bitmap &= !bit;
+                    }
+                }
+
+                if children.is_empty() {
+                    Some(None)
+                } else if shift > 0 && children.len() == 1 && matches!(children[0], Child::Leaf(_))
+                {
+                    Some(children.pop())
+                } else {
+                    let node = Node::Branch { bitmap, children };
+                    Some(Some(Child::Node(Arc::new(node))))
+                }
+            }
+            Node::Collision(leaves) => {
+                let i = leaves.iter().position(|leaf| leaf.key == *key)?;
+                let mut leaves = leaves.clone();
+                leaves.remove(i);
+                if leaves.len() == 1 {
+                    Some(leaves.pop().map(Child::Leaf))
+                } else {
+                    Some(Some(Child::Node(Arc::new(Node::Collision(leaves)))))
+                }
+            }
+        }
+    }
+
+    /// Appends the differences between two nodes at the same position.
+    fn diff<'a>(&'a self, other: &'a Self, diffs: &mut Vec<Diff<'a, K, V>>)
+    where
+        V: PartialEq,
+    {
+        let (
+            Node::Branch {
+                bitmap: bitmap_a,
+                children: children_a,
+            },
+            Node::Branch {
+                bitmap: bitmap_b,
+                children: children_b,
+            },
+        ) = (self, other)
+        else {
+            return diff_leaves(self.leaves(), other.leaves(), diffs);
+        };
+
+        let (mut a, mut b) = (children_a.iter(), children_b.iter());
+        for i in 0..u32::BITS {
+            let bit = 1 << i;
+            match (bitmap_a & bit != 0, bitmap_b & bit != 0) {
+                (true, true) => {
+                    let (a, b) = (a.next().unwrap(), b.next().unwrap());
+                    match (a, b) {
+                        (Child::Node(a), Child::Node(b)) if Arc::ptr_eq(a, b) => {}
+                        (Child::Node(a), Child::Node(b)) => a.diff(b, diffs),
+                        (Child::Leaf(a), Child::Leaf(b)) if Arc::ptr_eq(a, b) => {}
+                        _ => diff_leaves(a.leaves(), b.leaves(), diffs),
+                    }
+                }
+                (true, false) => {
+                    let leaves = a.next().unwrap().leaves();
+                    diffs.extend(
+                        leaves
+                            .into_iter()
+                            .map(|leaf| Diff::Removed(&leaf.key, &leaf.value)),
+                    );
+                }
+                (false, true) => {
+                    let leaves = b.next().unwrap().leaves();
+                    diffs.extend(
+                        leaves
+                            .into_iter()
+                            .map(|leaf| Diff::Added(&leaf.key, &leaf.value)),
+                    );
+                }
+                (false, false) => {}
+            }
+        }
+    }
+}
+
+impl<K, V> Node<K, V> {
+    fn leaves(&self) -> Vec<&Leaf<K, V>> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+
+    fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a Leaf<K, V>>) {
+        match self {
+            Node::Branch { children, .. } => {
+                for child in children {
+                    match child {
+                        Child::Leaf(leaf) => leaves.push(leaf),
+                        Child::Node(node) => node.collect_leaves(leaves),
+                    }
+                }
+            }
+            Node::Collision(collision) => leaves.extend(collision.iter().map(|leaf| &**leaf)),
+        }
+    }
+}
+
+impl<K, V> Child<K, V> {
+    fn leaves(&self) -> Vec<&Leaf<K, V>> {
+        match self {
+            Child::Leaf(leaf) => vec![leaf],
+            Child::Node(node) => node.leaves(),
+        }
+    }
+}
+
+/// Diffs two small sets of leaves by comparing every pair.
+fn diff_leaves<'a, K, V>(
+    a: Vec<&'a Leaf<K, V>>,
+    b: Vec<&'a Leaf<K, V>>,
+    diffs: &mut Vec<Diff<'a, K, V>>,
+) where
+    K: Eq,
+    V: PartialEq,
+{
+    for old in &a {
+        match b.iter().find(|new| new.key == old.key) {
+            Some(new) if new.value != old.value => {
+                diffs.push(Diff::Changed(&old.key, &old.value, &new.value))
+            }
+            Some(_) => {}
+            None => diffs.push(Diff::Removed(&old.key, &old.value)),
+        }
+    }
+    for new in &b {
+        if !a.iter().any(|old| old.key == new.key) {
+            diffs.push(Diff::Added(&new.key, &new.value));
+        }
+    }
+}
+
+fn hash<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the bitmap bit selected by `hash` at `shift`.
+fn bit(hash: u64, shift: u32) -> u32 {
+    1 << ((hash >> shift) & MASK)
+}
+
+/// Returns the position in a branch's children of the child at `bit`.
+fn index(bitmap: u32, bit: u32) -> usize {
+    (bitmap & (bit - 1)).count_ones() as usize
+}
+
+/// Clones share all nodes, so this is *O*(1).
+impl<K, V> Clone for PersistentHashMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for PersistentHashMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for PersistentHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |map, (key, value)| map.insert(key, value))
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    // Children of the branches on the path to the next entry.
+    stack: Vec<slice::Iter<'a, Child<K, V>>>,
+    collision: slice::Iter<'a, Arc<Leaf<K, V>>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(leaf) = self.collision.next() {
+                self.len -= 1;
+                return Some((&leaf.key, &leaf.value));
+            }
+            match self.stack.last_mut()?.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(Child::Leaf(leaf)) => {
+                    self.len -= 1;
+                    return Some((&leaf.key, &leaf.value));
+                }
+                Some(Child::Node(node)) => match &**node {
+                    Node::Branch { children, .. } => self.stack.push(children.iter()),
+                    Node::Collision(leaves) => self.collision = leaves.iter(),
+                },
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<'a, K, V> IntoIterator for &'a PersistentHashMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diff, PersistentHashMap};
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+
+    /// Key whose hash only depends on `bucket`, to force collisions.
+    #[derive(Debug, PartialEq, Eq)]
+    struct Colliding {
+        bucket: u8,
+        id: u32,
+    }
+
+    impl Hash for Colliding {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.bucket.hash(state);
+        }
+    }
+
+    #[test]
+    fn basic() {
+        let empty = PersistentHashMap::new();
+        let one = empty.insert("a", 1);
+        let two = one.insert("b", 2);
+        let replaced = two.insert("a", 10);
+
+        assert_eq!(empty.get(&"a"), None);
+        assert_eq!(one.get(&"a"), Some(&1));
+        assert_eq!(two.len(), 2);
+        assert_eq!(replaced.len(), 2);
+        assert_eq!(replaced.get(&"a"), Some(&10));
+        assert_eq!(two.get(&"a"), Some(&1));
+
+        let removed = replaced.remove(&"a");
+        assert_eq!(removed.len(), 1);
+        assert!(!removed.contains_key(&"a"));
+        assert!(replaced.contains_key(&"a"));
+        assert_eq!(removed.remove(&"missing").len(), 1);
+        assert!(removed.remove(&"b").is_empty());
+    }
+
+    #[test]
+    fn many() {
+        let mut map = PersistentHashMap::new();
+        let mut expected = HashMap::new();
+        for i in 0..5000 {
+            map = map.insert(i, i * 2);
+            expected.insert(i, i * 2);
+        }
+        for i in (0..5000).step_by(3) {
+            map = map.remove(&i);
+            expected.remove(&i);
+        }
+
+        assert_eq!(map.len(), expected.len());
+        assert_eq!(map.iter().len(), expected.len());
+        for (key, value) in &map {
+            assert_eq!(expected.get(key), Some(value));
+        }
+        for i in 0..5000 {
+            assert_eq!(map.get(&i), expected.get(&i));
+        }
+    }
+
+    #[test]
+    fn collisions() {
+        let key = |bucket, id| Colliding { bucket, id };
+        let map: PersistentHashMap<_, _> = (0..6).map(|id| (key(id as u8 % 2, id), id)).collect();
+        assert_eq!(map.len(), 6);
+        for id in 0..6 {
+            assert_eq!(map.get(&key(id as u8 % 2, id)), Some(&id));
+        }
+        assert_eq!(map.get(&key(0, 1)), None);
+
+        let map = map
+            .insert(key(0, 2), 20)
+            .remove(&key(0, 0))
+            .remove(&key(0, 4));
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&key(0, 2)), Some(&20));
+        assert_eq!(map.iter().count(), 4);
+
+        let map = map.remove(&key(0, 2));
+        assert_eq!(map.get(&key(1, 3)), Some(&3));
+        assert_eq!(map.get(&key(0, 2)), None);
+    }
+
+    #[test]
+    fn diff() {
+        let base: PersistentHashMap<_, _> = (0..1000).map(|i| (i, i)).collect();
+        let changed = base.insert(5, 50).remove(&7).insert(2000, 0);
+
+        let mut diffs = base.diff(&changed);
+        diffs.sort_by_key(|diff| match diff {
+            Diff::Added(key, _) | Diff::Removed(key, _) | Diff::Changed(key, _, _) => **key,
+        });
+        assert_eq!(
+            diffs,
+            vec![
+                Diff::Changed(&5, &5, &50),
+                Diff::Removed(&7, &7),
+                Diff::Added(&2000, &0),
+            ]
+        );
+        assert!(base.diff(&base.clone()).is_empty());
+        assert_eq!(changed.diff(&base).len(), 3);
+    }
+}
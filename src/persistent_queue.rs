@@ -0,0 +1,409 @@
+#![allow(unused)]
+//! Persistent FIFO queue after Okasaki's real-time queue.
+//!
+//! `back` is a persistent [`List`] of the newest elements in reverse. `front`
+//! is a lazily evaluated stream of the oldest elements, each cell computed at
+//! most once and then shared by every version that reaches it. Whenever
+//! `back` would grow longer than `front`, `front ++ reverse(back)` is set up
+//! as a suspended rotation that produces one element each time a cell is
+//! forced. `schedule` points at the first unforced cell of `front`, and every
+//! operation forces one more, so the rotation is finished before the next
+//! one starts. That makes `push_back` and `pop_front` *O*(1) in the worst
+//! case, no matter how often a version is built upon: branching off the same
+//! version right before a rotation shares the suspended rotation instead of
+//! repeating it.
+//!
+//! Nodes are shared through `Arc`, so versions can be sent to and read from
+//! other threads.
+use crate::singly_linked_stack::List;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::vec;
+
+pub struct Queue<T> {
+    front: Stream<T>,
+    front_len: usize,
+    back: List<T>,
+    // Suffix of `front` holding its unforced cells, `front_len - back.len()`
+    // long.
+    schedule: Stream<T>,
+}
+
+/// A lazily evaluated list whose cells are memoised once forced. `None` is
+/// the empty stream.
+type Stream<T> = Option<Arc<Cell<T>>>;
+
+struct Cell<T> {
+    value: OnceLock<(T, Stream<T>)>,
+    // Taken by whichever thread forces the cell first.
+    rotation: Mutex<Option<Rotation<T>>>,
+}
+
+/// The suspended `front ++ reverse(back) ++ acc`, where `back` is one longer
+/// than `front`.
+struct Rotation<T> {
+    front: Stream<T>,
+    back: List<T>,
+    acc: Stream<T>,
+    // `Rotation::step`, stored so forcing a cell doesn't need `T: Clone`.
+    step: fn(Rotation<T>) -> (T, Stream<T>),
+}
+
+#[cfg(test)]
+thread_local! {
+    // Rotation steps run on this thread, to check the work done per operation.
+    static STEPS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        Self {
+            front: None,
+            front_len: 0,
+            back: List::new(),
+            schedule: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front_len + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        // `front` is only empty if `back` is too.
+        self.front.is_none()
+    }
+
+    /// Returns the oldest element.
+    pub fn front(&self) -> Option<&T> {
+        self.front.as_ref().map(|cell| &cell.force().0)
+    }
+
+    /// Iterates from the oldest element to the newest.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let back: Vec<&T> = self.back.iter().collect();
+        Iter {
+            front: &self.front,
+            front_len: self.front_len,
+            back: back.into_iter().rev(),
+        }
+    }
+}
+
+impl<T: Clone> Queue<T> {
+    /// Returns a new version with `value` added at the back.
+    pub fn push_back(&self, value: T) -> Self {
+        Self::exec(
+            self.front.clone(),
+            self.front_len,
+            self.back.push(value),
+            &self.schedule,
+        )
+    }
+
+    /// Returns the oldest element and a new version without it, or `None` if
+    /// the queue is empty.
+    pub fn pop_front(&self) -> Option<(&T, Self)> {
+        let (value, front) = self.front.as_ref()?.force();
+        let queue = Self::exec(
+            front.clone(),
+            self.front_len - 1,
+            self.back.clone(),
+            &self.schedule,
+        );
+        Some((value, queue))
+    }
+
+    /// Forces the next scheduled cell, or starts a rotation once they're all
+    /// forced and `back` has become one longer than `front`.
+    fn exec(front: Stream<T>, front_len: usize, back: List<T>, schedule: &Stream<T>) -> Self {
+        match schedule {
+            Some(cell) => Self {
+                front,
+                front_len,
+                back,
+                schedule: cell.force().1.clone(),
+            },
+            None => {
+                let front_len = front_len + back.len();
+                let front = Rotation::start(front, back, None);
+                Self {
+                    front: front.clone(),
+                    front_len,
+                    back: List::new(),
+                    schedule: front,
+                }
+            }
+        }
+    }
+}
+
+impl<T> Cell<T> {
+    fn forced(value: T, rest: Stream<T>) -> Self {
+        Self {
+            value: OnceLock::from((value, rest)),
+            rotation: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cell's element and the rest of the stream, running its
+    /// rotation step the first time.
+    fn force(&self) -> &(T, Stream<T>) {
+        self.value.get_or_init(|| {
+            let rotation = self
+                .rotation
+                .lock()
+                .unwrap()
+                .take()
+                .expect("an unforced cell holds its rotation");
+            (rotation.step)(rotation)
+        })
+    }
+}
+
+/// Drops the rest of the stream iteratively, so long streams can't overflow
+/// the stack.
+impl<T> Drop for Cell<T> {
+    fn drop(&mut self) {
+        let mut next = self.value.take().and_then(|(_, rest)| rest);
+        while let Some(cell) = next {
+            next = match Arc::try_unwrap(cell) {
+                Ok(mut cell) => cell.value.take().and_then(|(_, rest)| rest),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+impl<T: Clone> Rotation<T> {
+    fn start(front: Stream<T>, back: List<T>, acc: Stream<T>) -> Stream<T> {
+        Some(Arc::new(Cell {
+            value: OnceLock::new(),
+            rotation: Mutex::new(Some(Self {
+                front,
+                back,
+                acc,
+                step: Self::step,
+            })),
+        }))
+    }
+
+    /// Produces the next element of the rotation and suspends the rest.
+    /// `front` has already been forced by the schedule, so this is *O*(1).
+    fn step(self) -> (T, Stream<T>) {
+        #[cfg(test)]
+        STEPS.with(|steps| steps.set(steps.get() + 1));
+
+        let Self {
+            front, back, acc, ..
+        } = self;
+        let (last, back) = back
+            .uncons()
+            .expect("a rotation's back is longer than its front");
+        match front {
+            None => (last.clone(), acc),
+            Some(cell) => {
+                let (value, front) = cell.force();
+                let acc = Some(Arc::new(Cell::forced(last.clone(), acc)));
+                (value.clone(), Self::start(front.clone(), back, acc))
+            }
+        }
+    }
+}
+
+/// Clones share all nodes, so this is *O*(1).
+impl<T> Clone for Queue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.clone(),
+            schedule: self.schedule.clone(),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Queue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Queue<T> {}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Queue<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Builds a queue whose oldest element is the first element of the iterator.
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let values: Vec<T> = iter.into_iter().collect();
+        let front_len = values.len();
+        let front = values.into_iter().rev().fold(None, |rest, value| {
+            Some(Arc::new(Cell::forced(value, rest)))
+        });
+        Self {
+            front: front.clone(),
+            front_len,
+            back: List::new(),
+            schedule: front,
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: &'a Stream<T>,
+    front_len: usize,
+    back: std::iter::Rev<vec::IntoIter<&'a T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.front {
+            Some(cell) => {
+                let (value, rest) = cell.force();
+                self.front = rest;
+                self.front_len -= 1;
+                Some(value)
+            }
+            None => self.back.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front_len + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Queue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Queue, STEPS};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let queue = Queue::new();
+        assert!(queue.pop_front().is_none());
+
+        let queue = queue.push_back(1).push_back(2).push_back(3);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.front(), Some(&1));
+
+        let (value, queue) = queue.pop_front().unwrap();
+        assert_eq!(*value, 1);
+        let queue = queue.push_back(4);
+        let (value, queue) = queue.pop_front().unwrap();
+        assert_eq!(*value, 2);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(format!("{:?}", queue), "[3, 4]");
+    }
+
+    #[test]
+    fn persistence() {
+        let base: Queue<_> = (0..5).collect();
+        let pushed = base.push_back(5);
+        let (_, popped) = base.pop_front().unwrap();
+
+        assert_eq!(base, (0..5).collect());
+        assert_eq!(pushed, (0..6).collect());
+        assert_eq!(popped, (1..5).collect());
+
+        // Both branches keep working independently
+        let a = popped.push_back(10);
+        let b = popped.push_back(20);
+        assert_eq!(a.iter().last(), Some(&10));
+        assert_eq!(b.iter().last(), Some(&20));
+        assert_eq!(popped.len(), 4);
+    }
+
+    #[test]
+    fn order() {
+        let mut queue = Queue::new();
+        let mut expected = std::collections::VecDeque::new();
+        for i in 0..100 {
+            queue = queue.push_back(i);
+            expected.push_back(i);
+            if i % 3 == 0 {
+                let (value, next) = queue.pop_front().unwrap();
+                assert_eq!(Some(*value), expected.pop_front());
+                queue = next;
+            }
+        }
+        assert!(queue.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn work_per_operation() {
+        fn steps<R>(f: impl FnOnce() -> R) -> (R, usize) {
+            let before = STEPS.with(|steps| steps.get());
+            let result = f();
+            (result, STEPS.with(|steps| steps.get()) - before)
+        }
+
+        // Find a version whose next push starts a rotation of `front`.
+        let mut queue = Queue::new();
+        for i in 0.. {
+            queue = queue.push_back(i);
+            if queue.schedule.is_none() && queue.back.len() == queue.front_len && i > 100 {
+                break;
+            }
+        }
+
+        // Branching off it starts a new rotation each time, but no single
+        // operation does more than constant work.
+        for branch in 0..10 {
+            let (mut version, work) = steps(|| queue.push_back(branch));
+            assert!(work <= 1);
+            while !version.is_empty() {
+                let (next, work) = steps(|| version.pop_front().unwrap().1);
+                assert!(work <= 2);
+                version = next;
+            }
+        }
+
+        // Branches off the same rotated version share its forced cells.
+        let rotated = queue.push_back(-1);
+        let drain = |mut version: Queue<i32>| {
+            steps(|| {
+                while let Some(next) = version.pop_front().map(|(_, next)| next) {
+                    version = next;
+                }
+            })
+            .1
+        };
+        assert!(drain(rotated.clone()) > 0);
+        assert_eq!(drain(rotated.clone()), 0);
+        assert_eq!(rotated.len(), queue.len() + 1);
+    }
+
+    #[test]
+    fn threads() {
+        let queue: Queue<_> = (0..10).collect();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let queue = queue.clone();
+                thread::spawn(move || queue.push_back(100 + i).len())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 11);
+        }
+        assert_eq!(queue.len(), 10);
+    }
+}
@@ -0,0 +1,453 @@
+#![allow(unused)]
+//! Persistent vector: a 32-way bit-partitioned trie, as in Clojure's
+//! `PersistentVector`.
+//!
+//! Element `i` is found by taking 5 bits of `i` per level, starting at the top
+//! of the trie. The last, possibly partial, leaf is kept outside the trie as
+//! `tail`, so most pushes and pops only touch that buffer. Updates copy the
+//! nodes on the path to the element and share everything else through `Arc`,
+//! which makes `get`/`set`/`push_back`/`pop_back` *O*(log32 n).
+//!
+//! Nodes are copied with `Arc::make_mut`, i.e. only while they're shared. A
+//! [`Transient`] owns the nodes it has already copied, so a batch of updates
+//! through it copies each node at most once instead of once per update.
+use std::fmt;
+use std::mem;
+use std::ops::Index;
+use std::slice;
+use std::sync::Arc;
+
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+#[derive(Clone)]
+enum Node<T> {
+    Branch(Vec<Arc<Node<T>>>),
+    Leaf(Vec<T>),
+}
+
+pub struct Vector<T> {
+    len: usize,
+    // Bit offset of the root's level: leaves are at level 0.
+    shift: usize,
+    root: Arc<Node<T>>,
+    tail: Arc<Vec<T>>,
+}
+
+impl<T> Vector<T> {
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            shift: BITS,
+            root: Arc::new(Node::Branch(Vec::new())),
+            tail: Arc::new(Vec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len {
+            return None;
+        }
+        self.leaf(i).get(i & MASK)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vector: self,
+            index: 0,
+            leaf: [].iter(),
+        }
+    }
+
+    /// Index of the first element in `tail`.
+    fn tail_offset(&self) -> usize {
+        if self.len < WIDTH {
+            0
+        } else {
+            ((self.len - 1) >> BITS) << BITS
+        }
+    }
+
+    /// Returns the leaf holding index `i`, which must be in bounds.
+    fn leaf(&self, i: usize) -> &[T] {
+        if i >= self.tail_offset() {
+            return &self.tail;
+        }
+
+        let mut node = &self.root;
+        let mut level = self.shift;
+        loop {
+            match &**node {
+                Node::Branch(children) => {
+                    node = &children[(i >> level) & MASK];
+                    level -= BITS;
+                }
+                Node::Leaf(values) => return values,
+            }
+        }
+    }
+}
+
+impl<T: Clone> Vector<T> {
+    /// Returns a new version with `value` at index `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn set(&self, i: usize, value: T) -> Self {
+        let mut vector = self.clone();
+        vector.set_mut(i, value);
+        vector
+    }
+
+    /// Returns a new version with `value` appended.
+    pub fn push_back(&self, value: T) -> Self {
+        let mut vector = self.clone();
+        vector.push_back_mut(value);
+        vector
+    }
+
+    /// Returns the last element and a new version without it, or `None` if
+    /// the vector is empty.
+    pub fn pop_back(&self) -> Option<(&T, Self)> {
+        let last = self.get(self.len.checked_sub(1)?)?;
+        let mut vector = self.clone();
+        vector.pop_back_mut();
+        Some((last, vector))
+    }
+
+    /// Returns a mutable copy for applying many updates at once.
+    pub fn transient(&self) -> Transient<T> {
+        Transient {
+            vector: self.clone(),
+        }
+    }
+
+    fn set_mut(&mut self, i: usize, value: T) {
+        assert!(i < self.len, "index out of bounds");
+        if i >= self.tail_offset() {
+            Arc::make_mut(&mut self.tail)[i & MASK] = value;
+            return;
+        }
+
+        let mut node = &mut self.root;
+        let mut level = self.shift;
+        loop {
+            match Arc::make_mut(node) {
+                Node::Branch(children) => {
+                    node = &mut children[(i >> level) & MASK];
+                    level -= BITS;
+                }
+                Node::Leaf(values) => {
+                    values[i & MASK] = value;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn push_back_mut(&mut self, value: T) {
+        if self.len - self.tail_offset() < WIDTH {
+            Arc::make_mut(&mut self.tail).push(value);
+            self.len += 1;
+            return;
+        }
+
+        // The tail is full: move it into the trie and start a new one.
+        let tail = Arc::unwrap_or_clone(mem::replace(&mut self.tail, Arc::new(vec![value])));
+        let leaf = Arc::new(Node::Leaf(tail));
+        if (self.len >> BITS) > (1 << self.shift) {
+            // The trie is full too, add a level on top.
+            let path = new_path(self.shift, leaf);
+            let root = mem::replace(&mut self.root, Arc::new(Node::Branch(Vec::new())));
+            self.root = Arc::new(Node::Branch(vec![root, path]));
+            self.shift += BITS;
+        } else {
+            push_leaf(&mut self.root, self.shift, self.len, leaf);
+        }
+        self.len += 1;
+    }
+
+    fn pop_back_mut(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        if self.len - self.tail_offset() > 1 || self.len == 1 {
+            self.len -= 1;
+            return Arc::make_mut(&mut self.tail).pop();
+        }
+
+        // The tail is about to become empty: take the last leaf out of the
+        // trie to replace it.
+        let value = Arc::make_mut(&mut self.tail).pop();
+        self.tail = Arc::new(pop_leaf(&mut self.root, self.shift));
+        if self.shift > BITS {
+            if let Node::Branch(children) = &*self.root {
+                if children.len() == 1 {
+                    self.root = children[0].clone();
+                    self.shift -= BITS;
+                }
+            }
+        }
+        self.len -= 1;
+        value
+    }
+}
+
+/// Wraps `leaf` in single-child branches up to `level`.
+fn new_path<T>(level: usize, leaf: Arc<Node<T>>) -> Arc<Node<T>> {
+    if level == 0 {
+        leaf
+    } else {
+        Arc::new(Node::Branch(vec![new_path(level - BITS, leaf)]))
+    }
+}
+
+/// Appends `leaf` as the rightmost leaf below `node`, where `len` is the
+/// vector's length including the full tail that `leaf` was made from.
+fn push_leaf<T: Clone>(node: &mut Arc<Node<T>>, level: usize, len: usize, leaf: Arc<Node<T>>) {
+    let Node::Branch(children) = Arc::make_mut(node) else {
+        unreachable!("leaf above level 0");
+    };
+    let sub = ((len - 1) >> level) & MASK;
+    if level == BITS {
+        children.push(leaf);
+    } else if sub < children.len() {
+        push_leaf(&mut children[sub], level - BITS, len, leaf);
+    } else {
+        children.push(new_path(level - BITS, leaf));
+    }
+}
+
+/// Removes the rightmost leaf below `node` and returns its values. Branches
+/// left empty are removed as well.
+fn pop_leaf<T: Clone>(node: &mut Arc<Node<T>>, level: usize) -> Vec<T> {
+    let Node::Branch(children) = Arc::make_mut(node) else {
+        unreachable!("leaf above level 0");
+    };
+    if level == BITS {
+        match Arc::unwrap_or_clone(children.pop().unwrap()) {
+            Node::Leaf(values) => values,
+            Node::Branch(_) => unreachable!("branch at level 0"),
+        }
+    } else {
+        let child = children.last_mut().unwrap();
+        let values = pop_leaf(child, level - BITS);
+        if matches!(&**child, Node::Branch(grandchildren) if grandchildren.is_empty()) {
+            children.pop();
+        }
+        values
+    }
+}
+
+/// Clones share all nodes, so this is *O*(1).
+impl<T> Clone for Vector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            len: self.len,
+            shift: self.shift,
+            root: self.root.clone(),
+            tail: self.tail.clone(),
+        }
+    }
+}
+
+impl<T> Index<usize> for Vector<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("index out of bounds")
+    }
+}
+
+impl<T: PartialEq> PartialEq for Vector<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Vector<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Vector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone> FromIterator<T> for Vector<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut transient = Vector::new().transient();
+        for value in iter {
+            transient.push_back(value);
+        }
+        transient.persistent()
+    }
+}
+
+/// A [`Vector`] being updated in place. Turn it back into a persistent
+/// vector with [`Transient::persistent`].
+pub struct Transient<T> {
+    vector: Vector<T>,
+}
+
+impl<T: Clone> Transient<T> {
+    pub fn len(&self) -> usize {
+        self.vector.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vector.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.vector.get(i)
+    }
+
+    /// Replaces the element at index `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) {
+        self.vector.set_mut(i, value)
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.vector.push_back_mut(value)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.vector.pop_back_mut()
+    }
+
+    pub fn persistent(self) -> Vector<T> {
+        self.vector
+    }
+}
+
+pub struct Iter<'a, T> {
+    vector: &'a Vector<T>,
+    // Index of the first element after `leaf`.
+    index: usize,
+    leaf: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.leaf.next() {
+            return Some(value);
+        }
+        if self.index >= self.vector.len {
+            return None;
+        }
+        let leaf = self.vector.leaf(self.index);
+        self.index += leaf.len();
+        self.leaf = leaf.iter();
+        self.leaf.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.vector.len - self.index + self.leaf.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Vector<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Vector;
+
+    #[test]
+    fn basics() {
+        let vector = Vector::new();
+        assert_eq!(vector.get(0), None);
+        assert!(vector.pop_back().is_none());
+
+        let vector = vector.push_back(1).push_back(2).push_back(3);
+        assert_eq!(vector.len(), 3);
+        assert_eq!(vector[1], 2);
+
+        let updated = vector.set(1, 20);
+        assert_eq!(updated.iter().copied().collect::<Vec<_>>(), vec![1, 20, 3]);
+        assert_eq!(vector.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let (last, popped) = vector.pop_back().unwrap();
+        assert_eq!(*last, 3);
+        assert_eq!(popped.len(), 2);
+        assert_eq!(format!("{:?}", popped), "[1, 2]");
+    }
+
+    #[test]
+    fn deep() {
+        // Enough for a trie of three levels
+        let n = 40_000;
+        let mut versions = Vec::new();
+        let mut vector = Vector::new();
+        for i in 0..n {
+            if i % 1000 == 0 {
+                versions.push(vector.clone());
+            }
+            vector = vector.push_back(i);
+        }
+        assert_eq!(vector.len(), n);
+        assert!((0..n).all(|i| vector[i] == i));
+        assert!(vector.iter().copied().eq(0..n));
+
+        let updated = vector.set(12_345, 0).set(n - 1, 0);
+        assert_eq!((updated[12_345], updated[n - 1]), (0, 0));
+        assert_eq!((vector[12_345], vector[n - 1]), (12_345, n - 1));
+
+        // Old versions are untouched
+        for (k, version) in versions.iter().enumerate() {
+            assert_eq!(version.len(), k * 1000);
+            assert!(version.iter().copied().eq(0..k * 1000));
+        }
+
+        for i in (0..n).rev() {
+            let (last, popped) = vector.pop_back().unwrap();
+            assert_eq!(*last, i);
+            vector = popped;
+        }
+        assert!(vector.is_empty());
+    }
+
+    #[test]
+    fn transient() {
+        let vector: Vector<_> = (0..2000).collect();
+        let mut transient = vector.transient();
+        for i in 0..2000 {
+            transient.set(i, i * 2);
+        }
+        for _ in 0..500 {
+            transient.pop_back();
+        }
+        transient.push_back(0);
+        let doubled = transient.persistent();
+
+        assert_eq!(doubled.len(), 1501);
+        assert_eq!(doubled[1499], 2998);
+        assert_eq!(doubled[1500], 0);
+        assert!(vector.iter().copied().eq(0..2000));
+    }
+}
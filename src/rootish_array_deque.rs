@@ -0,0 +1,161 @@
+#![allow(unused)]
+//! RootishArrayDeque: the DualArrayDeque layout with two RootishArrayStacks in
+//! place of the ArrayStacks.
+//!
+//! Operations cost the same as in the DualArrayDeque, but since each half
+//! wastes only *O*(sqrt n) space, so does the deque.
+use crate::dual_array_deque::Halves;
+use crate::rootish_array_stack::{self, RootishStack};
+use std::ops::{Index, IndexMut};
+use std::{iter, mem};
+
+pub struct RootishArrayDeque<T> {
+    halves: Halves<RootishStack<T>>,
+}
+
+impl<T> RootishArrayDeque<T> {
+    pub fn new() -> Self {
+        Self {
+            halves: Halves::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.halves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.halves.get(i)
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.halves.get_mut(i)
+    }
+
+    /// Replaces the element at index `i` and returns the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) -> T {
+        mem::replace(&mut self[i], value)
+    }
+
+    /// Inserts an element at index `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is greater than the deque's length.
+    pub fn add(&mut self, i: usize, value: T) {
+        self.halves.add(i, value)
+    }
+
+    /// Removes and returns the element at index `i`, or returns `None` if `i`
+    /// is out of bounds.
+    pub fn remove(&mut self, i: usize) -> Option<T> {
+        self.halves.remove(i)
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.add(0, value)
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.add(self.len(), value)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.len().checked_sub(1).and_then(|i| self.remove(i))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.halves
+            .front
+            .iter()
+            .rev()
+            .chain(self.halves.back.iter())
+    }
+}
+
+pub type Iter<'a, T> =
+    iter::Chain<iter::Rev<rootish_array_stack::Iter<'a, T>>, rootish_array_stack::Iter<'a, T>>;
+
+impl<T> Index<usize> for RootishArrayDeque<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for RootishArrayDeque<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        self.get_mut(i).expect("index out of bounds")
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RootishArrayDeque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RootishArrayDeque;
+
+    fn to_vec(deque: &RootishArrayDeque<usize>) -> Vec<usize> {
+        deque.iter().copied().collect()
+    }
+
+    #[test]
+    fn basic() {
+        let mut deque = RootishArrayDeque::new();
+        deque.push_back(2);
+        deque.push_front(0);
+        deque.add(1, 1);
+        deque.push_back(3);
+        assert_eq!(to_vec(&deque), vec![0, 1, 2, 3]);
+
+        assert_eq!(deque.set(1, 10), 1);
+        assert_eq!(deque.remove(2), Some(2));
+        assert_eq!(deque.remove(3), None);
+        assert_eq!(to_vec(&deque), vec![0, 10, 3]);
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_back(), Some(10));
+        assert_eq!(deque.pop_back(), None);
+    }
+
+    #[test]
+    fn add_remove() {
+        let mut deque = RootishArrayDeque::new();
+        let mut expected = Vec::new();
+        for i in 0..50 {
+            let at = (i * 7) % (expected.len() + 1);
+            deque.add(at, i);
+            expected.insert(at, i);
+        }
+        assert_eq!(to_vec(&deque), expected);
+
+        for i in 0..25 {
+            let at = (i * 5) % expected.len();
+            assert_eq!(deque.remove(at), Some(expected.remove(at)));
+        }
+        assert_eq!(to_vec(&deque), expected);
+        assert!(deque.iter().rev().eq(expected.iter().rev()));
+        assert_eq!((&deque).into_iter().count(), expected.len());
+    }
+}
@@ -1,7 +1,18 @@
 #![allow(unused)]
+//! RootishArrayStack: a list stored in blocks of size 1, 2, 3, ..., as
+//! described in
+//! [Open Data Structures](https://opendatastructures.org/ods-java/2_6_RootishArrayStack_Space.html).
+//!
+//! Block `b` holds list indices `b(b+1)/2 ..= b(b+1)/2 + b`, so elements never
+//! move when the list grows and at most *O*(sqrt n) slots are unused. `get`/
+//! `set` run in *O*(1), `add(i)`/`remove(i)` in *O*(1 + n - i).
+use std::ops::{Index, IndexMut};
+use std::{iter, slice, vec};
 
 pub struct RootishStack<T> {
     len: usize,
+    // Every block but the last non-empty one is full. At most one empty
+    // block is kept around after it.
     blocks: Vec<Vec<T>>,
 }
 
@@ -21,41 +32,286 @@ impl<T> RootishStack<T> {
         self.len
     }
 
-    pub fn push(&mut self, value: T) {
-        let (block, index) = (self.get_block(), self.get_index());
-        if index == 0 {
-            self.blocks
-                .push(Vec::with_capacity(std::cmp::max(block, 1)));
-            self.blocks[block].push(value);
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len {
+            return None;
+        }
+        let (block, index) = Self::locate(i);
+        self.blocks[block].get(index)
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        if i >= self.len {
+            return None;
+        }
+        let (block, index) = Self::locate(i);
+        self.blocks[block].get_mut(index)
+    }
+
+    /// Replaces the element at index `i` and returns the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) -> T {
+        std::mem::replace(&mut self[i], value)
+    }
+
+    /// Swaps the elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len && j < self.len, "index out of bounds");
+        let ((a, x), (b, y)) = (Self::locate(i.min(j)), Self::locate(i.max(j)));
+        if a == b {
+            self.blocks[a].swap(x, y);
         } else {
-            self.blocks[block].push(value);
+            let (head, tail) = self.blocks.split_at_mut(b);
+            std::mem::swap(&mut head[a][x], &mut tail[0][y]);
+        }
+    }
+
+    /// Inserts an element at index `i`, shifting all elements after it to the
+    /// right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is greater than the stack's length.
+    pub fn add(&mut self, i: usize, value: T) {
+        assert!(i <= self.len, "index out of bounds");
+        self.push(value);
+
+        // Carry the new element from the end down to `i`, one block at a time.
+        let (first, index) = Self::locate(i);
+        let last = i2b(self.len - 1);
+        for block in (first + 1..=last).rev() {
+            self.blocks[block].rotate_right(1);
+            let (head, tail) = self.blocks.split_at_mut(block);
+            std::mem::swap(head[block - 1].last_mut().unwrap(), &mut tail[0][0]);
+        }
+        self.blocks[first][index..].rotate_right(1);
+    }
+
+    /// Removes and returns the element at index `i`, shifting all elements
+    /// after it to the left, or returns `None` if `i` is out of bounds.
+    pub fn remove(&mut self, i: usize) -> Option<T> {
+        if i >= self.len {
+            return None;
         }
+
+        // Carry the removed element up to the end, then pop it.
+        let (first, index) = Self::locate(i);
+        let last = i2b(self.len - 1);
+        self.blocks[first][index..].rotate_left(1);
+        for block in first + 1..=last {
+            let (head, tail) = self.blocks.split_at_mut(block);
+            std::mem::swap(head[block - 1].last_mut().unwrap(), &mut tail[0][0]);
+            self.blocks[block].rotate_left(1);
+        }
+        self.pop()
+    }
+
+    pub fn push(&mut self, value: T) {
+        let block = self.get_block();
+        if block == self.blocks.len() {
+            self.blocks.push(Vec::with_capacity(block + 1));
+        }
+        self.blocks[block].push(value);
         self.len += 1;
     }
 
     pub fn pop(&mut self) -> Option<T> {
         if self.is_empty() {
-            None
-        } else {
-            self.len -= 1;
-            let block = self.get_block();
-            self.blocks[block].pop()
+            return None;
+        }
+        self.len -= 1;
+        let block = self.get_block();
+        let value = self.blocks[block].pop();
+        self.shrink();
+        value
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            blocks: self.blocks.iter().flatten(),
+            len: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            blocks: self.blocks.iter_mut().flatten(),
+            len: self.len,
         }
     }
 
+    /// Frees trailing blocks while at least two of them are empty.
+    fn shrink(&mut self) {
+        let mut blocks = self.blocks.len();
+        while blocks >= 2 && (blocks - 2) * (blocks - 1) / 2 >= self.len {
+            self.blocks.pop();
+            blocks -= 1;
+        }
+    }
+
+    /// Maps a list index to its block and the index within that block.
+    fn locate(i: usize) -> (usize, usize) {
+        let block = i2b(i);
+        (block, i - block * (block + 1) / 2)
+    }
+
     fn get_block(&self) -> usize {
-        ((-3.0 + f64::sqrt(9.0 + 8.0 * self.len as f64)) / 2.0).ceil() as usize
+        Self::locate(self.len).0
     }
 
     fn get_index(&self) -> usize {
-        let block = self.get_block();
-        self.len - block * (block + 1) / 2
+        Self::locate(self.len).1
+    }
+}
+
+/// Returns the block holding list index `i`: the largest `b` with
+/// `b(b+1)/2 <= i`. Computed in integers so it stays exact for any `i`.
+pub(crate) fn i2b(i: usize) -> usize {
+    let root = (8 * i as u128 + 1).isqrt();
+    ((root - 1) / 2) as usize
+}
+
+impl<T> Index<usize> for RootishStack<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for RootishStack<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        self.get_mut(i).expect("index out of bounds")
+    }
+}
+
+pub struct Iter<'a, T> {
+    blocks: iter::Flatten<slice::Iter<'a, Vec<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.blocks.next()?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let value = self.blocks.next_back()?;
+        self.len -= 1;
+        Some(value)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    blocks: iter::Flatten<slice::IterMut<'a, Vec<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.blocks.next()?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let value = self.blocks.next_back()?;
+        self.len -= 1;
+        Some(value)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+pub struct IntoIter<T> {
+    blocks: iter::Flatten<vec::IntoIter<Vec<T>>>,
+    len: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.blocks.next()?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let value = self.blocks.next_back()?;
+        self.len -= 1;
+        Some(value)
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<'a, T> IntoIterator for &'a RootishStack<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut RootishStack<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> IntoIterator for RootishStack<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            blocks: self.blocks.into_iter().flatten(),
+            len: self.len,
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::RootishStack;
+    use super::{i2b, RootishStack};
 
     #[test]
     fn basic() {
@@ -85,4 +341,70 @@ mod test {
         assert_eq!(stack.pop(), None);
         assert_eq!(stack.len(), 0);
     }
+
+    #[test]
+    fn block_index() {
+        for b in 0..1000 {
+            let start = b * (b + 1) / 2;
+            assert_eq!(i2b(start), b);
+            assert_eq!(i2b(start + b), b);
+        }
+
+        // Past 2^53, where f64 can't represent every index
+        let b = 1 << 31;
+        let start = b * (b + 1) / 2;
+        assert_eq!(i2b(start - 1), b - 1);
+        assert_eq!(i2b(start), b);
+    }
+
+    #[test]
+    fn list() {
+        let mut stack = RootishStack::new();
+        let mut expected = Vec::new();
+        for i in 0..40 {
+            let at = (i * 7) % (expected.len() + 1);
+            stack.add(at, i);
+            expected.insert(at, i);
+        }
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), expected);
+        assert_eq!(stack[17], expected[17]);
+        assert_eq!(stack.set(5, 100), expected[5]);
+        expected[5] = 100;
+
+        for i in 0..20 {
+            let at = (i * 5) % expected.len();
+            assert_eq!(stack.remove(at), Some(expected.remove(at)));
+        }
+        assert_eq!(stack.remove(20), None);
+        stack.swap(2, 15);
+        stack.swap(4, 4);
+        expected.swap(2, 15);
+
+        expected.reverse();
+        assert_eq!(stack.into_iter().rev().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn shrink() {
+        let mut stack = RootishStack::new();
+        for i in 0..100 {
+            stack.push(i);
+        }
+        assert_eq!(stack.blocks.len(), 14);
+
+        // 10 elements fill blocks 0..=3, plus one spare empty block
+        for _ in 0..90 {
+            stack.pop();
+        }
+        assert_eq!(stack.blocks.len(), 5);
+
+        while stack.pop().is_some() {}
+        assert_eq!(stack.blocks.len(), 1);
+
+        stack.push(7);
+        for value in stack.iter_mut() {
+            *value += 1;
+        }
+        assert_eq!(stack.iter().collect::<Vec<_>>(), vec![&8]);
+    }
 }
@@ -0,0 +1,296 @@
+#![allow(unused)]
+//! An append-only vector whose elements never move, so `push` only needs
+//! `&self` and the returned references stay valid while more elements are
+//! pushed, from any number of threads.
+//!
+//! Elements are laid out in blocks of size 1, 2, 3, ... like
+//! [`RootishStack`](crate::rootish_array_stack::RootishStack). Blocks are
+//! allocated on first use and their pointers kept in a directory of chunks,
+//! where chunk `k` holds the pointers of `2^k` consecutive blocks, so the
+//! directory never has to move either. Each push claims an index with a single
+//! `fetch_add`; whoever first needs a block or chunk allocates it and installs
+//! it with a compare-exchange. A slot's `ready` flag is set with `Release`
+//! once its value is written, and readers only hand out slots whose flag they
+//! observed with `Acquire`.
+use crate::rootish_array_stack::i2b;
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::ops::Index;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+const DIRECTORY_LEN: usize = usize::BITS as usize;
+
+struct Slot<T> {
+    ready: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    fn new() -> Self {
+        Self {
+            ready: AtomicBool::new(false),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+pub struct SegmentedVec<T> {
+    // Chunk `k` is an array of `2^k` block pointers, block `b` an array of
+    // `b + 1` slots. Null until allocated.
+    directory: [AtomicPtr<AtomicPtr<Slot<T>>>; DIRECTORY_LEN],
+    // Next index to hand out.
+    next: AtomicUsize,
+    // Number of completed pushes.
+    len: AtomicUsize,
+}
+
+// Values are written once by the pushing thread and only shared as `&T`
+// afterwards.
+unsafe impl<T: Send + Sync> Sync for SegmentedVec<T> {}
+unsafe impl<T: Send> Send for SegmentedVec<T> {}
+
+impl<T> SegmentedVec<T> {
+    pub fn new() -> Self {
+        Self {
+            directory: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
+            next: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of completed pushes. While other threads are
+    /// pushing, some indices below `len` may still be unwritten.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends an element and returns a reference to it.
+    pub fn push(&self, value: T) -> &T {
+        let i = self.next.fetch_add(1, Ordering::Relaxed);
+        let slot = self.slot(i, true).unwrap();
+        unsafe {
+            (*slot.value.get()).write(value);
+        }
+        slot.ready.store(true, Ordering::Release);
+        self.len.fetch_add(1, Ordering::Release);
+        unsafe { (*slot.value.get()).assume_init_ref() }
+    }
+
+    /// Returns the element at index `i`, or `None` if it hasn't been pushed
+    /// yet.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        let slot = self.slot(i, false)?;
+        if slot.ready.load(Ordering::Acquire) {
+            Some(unsafe { (*slot.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        let slot = self.slot(i, false)?;
+        if slot.ready.load(Ordering::Relaxed) {
+            Some(unsafe { (*slot.value.get()).assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the elements pushed before the call, in index order.
+    /// Elements still being written by other threads are skipped.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vec: self,
+            index: 0,
+            end: self.next.load(Ordering::Acquire),
+        }
+    }
+
+    /// Returns the slot for index `i`, allocating its block and chunk if
+    /// `alloc` is set, or `None` if they don't exist.
+    fn slot(&self, i: usize, alloc: bool) -> Option<&Slot<T>> {
+        let block = i2b(i);
+        let index = i - block * (block + 1) / 2;
+        let chunk = (block + 1).ilog2() as usize;
+        let block_in_chunk = block + 1 - (1 << chunk);
+
+        let blocks = if alloc {
+            get_or_alloc(&self.directory[chunk], 1 << chunk, || {
+                AtomicPtr::new(ptr::null_mut())
+            })
+        } else {
+            non_null(self.directory[chunk].load(Ordering::Acquire))?
+        };
+        let block_ptr = unsafe { &*blocks.add(block_in_chunk) };
+        let slots = if alloc {
+            get_or_alloc(block_ptr, block + 1, Slot::new)
+        } else {
+            non_null(block_ptr.load(Ordering::Acquire))?
+        };
+        Some(unsafe { &*slots.add(index) })
+    }
+}
+
+/// Returns the array behind `ptr`, first allocating one of `len` elements if
+/// it's null. If another thread installs an array concurrently, ours is freed
+/// and theirs returned.
+fn get_or_alloc<U>(ptr: &AtomicPtr<U>, len: usize, init: fn() -> U) -> *mut U {
+    let current = ptr.load(Ordering::Acquire);
+    if !current.is_null() {
+        return current;
+    }
+
+    let new = Box::into_raw((0..len).map(|_| init()).collect::<Box<[U]>>()) as *mut U;
+    match ptr.compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => new,
+        Err(winner) => {
+            drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(new, len)) });
+            winner
+        }
+    }
+}
+
+fn non_null<U>(ptr: *mut U) -> Option<*mut U> {
+    (!ptr.is_null()).then_some(ptr)
+}
+
+impl<T> Drop for SegmentedVec<T> {
+    fn drop(&mut self) {
+        for (chunk, blocks) in self.directory.iter_mut().enumerate() {
+            let blocks = *blocks.get_mut();
+            if blocks.is_null() {
+                continue;
+            }
+            let blocks =
+                unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(blocks, 1 << chunk)) };
+
+            for (j, mut slots) in blocks.into_vec().into_iter().enumerate() {
+                let slots = *slots.get_mut();
+                if slots.is_null() {
+                    continue;
+                }
+                let len = (1 << chunk) + j;
+                let mut slots = unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(slots, len)) };
+                for slot in slots.iter_mut() {
+                    if *slot.ready.get_mut() {
+                        unsafe { slot.value.get_mut().assume_init_drop() };
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> Index<usize> for SegmentedVec<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("index out of bounds")
+    }
+}
+
+pub struct Iter<'a, T> {
+    vec: &'a SegmentedVec<T>,
+    index: usize,
+    end: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.end {
+            self.index += 1;
+            if let Some(value) = self.vec.get(self.index - 1) {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.index))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SegmentedVec<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SegmentedVec;
+    use std::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn basic() {
+        let vec = SegmentedVec::new();
+        assert!(vec.is_empty());
+        assert_eq!(vec.get(0), None);
+
+        // References stay valid while pushing
+        let first = vec.push(String::from("first"));
+        let refs: Vec<&String> = (1..100).map(|i| vec.push(i.to_string())).collect();
+        assert_eq!(first, "first");
+        assert_eq!(refs[49], "50");
+
+        assert_eq!(vec.len(), 100);
+        assert_eq!(vec[0], "first");
+        assert_eq!(vec.get(99).map(String::as_str), Some("99"));
+        assert_eq!(vec.get(100), None);
+        assert_eq!(vec.get(10_000), None);
+        assert_eq!(vec.iter().count(), 100);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut vec = SegmentedVec::new();
+        vec.push(1);
+        vec.push(2);
+        *vec.get_mut(1).unwrap() += 10;
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec![1, 12]);
+    }
+
+    #[test]
+    fn concurrent() {
+        let vec = SegmentedVec::new();
+        thread::scope(|s| {
+            for t in 0..4 {
+                let vec = &vec;
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        assert_eq!(*vec.push(t * 1000 + i), t * 1000 + i);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(vec.len(), 4000);
+        let mut values: Vec<_> = vec.iter().copied().collect();
+        values.sort();
+        assert_eq!(values, (0..4000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drop_values() {
+        let value = Rc::new(());
+        let vec = SegmentedVec::new();
+        for _ in 0..20 {
+            vec.push(value.clone());
+        }
+        assert_eq!(Rc::strong_count(&value), 21);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+}
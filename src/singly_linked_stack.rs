@@ -1,4 +1,5 @@
 #![allow(unused)]
+use std::hash::{Hash, Hasher};
 // Use Arc for multi-threaded cases.
 use std::sync::Arc;
 
@@ -9,6 +10,7 @@ type Link<T> = Option<Arc<Node<T>>>;
 
 pub struct List<T> {
     head: Link<T>,
+    len: usize,
 }
 
 struct Node<T> {
@@ -18,7 +20,7 @@ struct Node<T> {
 
 impl<T> List<T> {
     pub fn new() -> Self {
-        Self { head: None }
+        Self { head: None, len: 0 }
     }
 
     pub fn push(&self, value: T) -> Self {
@@ -27,28 +29,125 @@ impl<T> List<T> {
                 next: self.head.clone(),
                 value,
             })),
+            len: self.len + 1,
         }
     }
 
     pub fn pop(&self) -> Self {
-        Self {
-            head: self.head.as_ref().and_then(|node| node.next.clone()),
+        match self.uncons() {
+            Some((_, tail)) => tail,
+            None => Self::new(),
         }
     }
 
+    /// Splits the list into its head value and the list after it, which
+    /// shares its nodes with this one.
+    pub fn uncons(&self) -> Option<(&T, Self)> {
+        self.head.as_ref().map(|node| {
+            let tail = Self {
+                head: node.next.clone(),
+                len: self.len - 1,
+            };
+            (&node.value, tail)
+        })
+    }
+
     pub fn peek(&self) -> Option<&T> {
         self.head.as_ref().map(|node| &node.value)
     }
 
-    fn iter(&self) -> Iter<T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             next: self.head.as_deref(),
+            len: self.len,
+        }
+    }
+
+    /// Returns `true` if both lists start at the same node, so they share
+    /// all their nodes.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        match (&a.head, &b.head) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: Clone> List<T> {
+    /// Returns a list with the elements of `self` followed by those of
+    /// `other`. Only `self`'s nodes are copied; `other` becomes the shared
+    /// tail of the result.
+    pub fn append(&self, other: &Self) -> Self {
+        let values: Vec<&T> = self.iter().collect();
+        values
+            .into_iter()
+            .rev()
+            .fold(other.clone(), |list, value| list.push(value.clone()))
+    }
+
+    /// Returns a list with the elements in reverse order.
+    pub fn reverse(&self) -> Self {
+        self.iter()
+            .fold(Self::new(), |list, value| list.push(value.clone()))
+    }
+}
+
+/// Clones share all nodes, so this is *O*(1).
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head.clone(),
+            len: self.len,
         }
     }
 }
 
-struct Iter<'a, T> {
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for value in self.iter() {
+            value.hash(state);
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Builds a list whose head is the first element of the iterator.
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let values: Vec<T> = iter.into_iter().collect();
+        values
+            .into_iter()
+            .rev()
+            .fold(Self::new(), |list, value| list.push(value))
+    }
+}
+
+pub struct Iter<'a, T> {
     next: Option<&'a Node<T>>,
+    len: usize,
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
@@ -57,9 +156,25 @@ impl<'a, T> Iterator for Iter<'a, T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.next.map(|node| {
             self.next = node.next.as_deref();
+            self.len -= 1;
             &node.value
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl<T> Drop for List<T> {
@@ -109,4 +224,47 @@ mod test {
         assert_eq!(iter.next(), Some(&1));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn uncons() {
+        let list = List::new().push(1).push(2);
+        let (head, tail) = list.uncons().unwrap();
+        assert_eq!(*head, 2);
+        assert_eq!(tail.len(), 1);
+        assert!(List::ptr_eq(&tail, &list.pop()));
+        assert!(List::<i32>::new().uncons().is_none());
+    }
+
+    #[test]
+    fn sharing() {
+        let tail: List<_> = (1..=3).collect();
+        let list = tail.push(0);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(list.len(), 4);
+        assert!(List::ptr_eq(&list.pop(), &tail));
+        assert!(List::ptr_eq(&list.clone(), &list));
+        assert!(!List::ptr_eq(&list, &tail));
+
+        let front: List<_> = vec![-2, -1].into_iter().collect();
+        let joined = front.append(&list);
+        assert_eq!(joined.len(), 6);
+        assert_eq!(joined, (-2..=3).collect());
+        assert!(List::ptr_eq(&joined.pop().pop(), &list));
+
+        let reversed = joined.reverse();
+        assert_eq!(reversed, (-2..=3).rev().collect());
+        assert_eq!(format!("{:?}", tail), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::HashSet;
+
+        let a: List<_> = (1..=3).collect();
+        let b = List::new().push(3).push(2).push(1);
+        let mut set = HashSet::new();
+        set.insert(a);
+        assert!(set.contains(&b));
+        assert!(!set.contains(&b.pop()));
+    }
 }
@@ -0,0 +1,425 @@
+#![allow(unused)]
+//! Slab: an array of slots handing out stable `usize` keys.
+//!
+//! Removing a value leaves a hole that is linked into a free list and reused
+//! by the next insert, so keys of other values never change. Insert, remove
+//! and lookup are all *O*(1).
+use std::mem;
+use std::ops::{Index, IndexMut};
+
+enum Entry<T> {
+    Occupied(T),
+    // Index of the next vacant slot, or `entries.len()` if this is the last.
+    Vacant(usize),
+}
+
+pub struct Slab<T> {
+    entries: Vec<Entry<T>>,
+    len: usize,
+    // Head of the free list, `entries.len()` when there are no holes.
+    next: usize,
+}
+
+impl<T> Slab<T> {
+    pub fn new() -> Self {
+        Slab::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of values the slab can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// Stores a value and returns its key.
+    pub fn insert(&mut self, value: T) -> usize {
+        let key = self.next;
+        self.insert_at(key, value);
+        key
+    }
+
+    /// Returns a handle to the slot the next insert will use, so the key can
+    /// be stored inside the value.
+    pub fn vacant_entry(&mut self) -> VacantEntry<'_, T> {
+        VacantEntry {
+            key: self.next,
+            slab: self,
+        }
+    }
+
+    /// Removes the value stored under `key` and returns it, or `None` if the
+    /// slot is vacant.
+    pub fn remove(&mut self, key: usize) -> Option<T> {
+        match self.entries.get_mut(key) {
+            Some(entry @ Entry::Occupied(_)) => {
+                let old = mem::replace(entry, Entry::Vacant(self.next));
+                self.next = key;
+                self.len -= 1;
+                match old {
+                    Entry::Occupied(value) => Some(value),
+                    Entry::Vacant(_) => unreachable!(),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: usize) -> Option<&T> {
+        match self.entries.get(key) {
+            Some(Entry::Occupied(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        match self.entries.get_mut(key) {
+            Some(Entry::Occupied(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns mutable references to the values under two keys at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` and `b` are the same key.
+    pub fn get2_mut(&mut self, a: usize, b: usize) -> (Option<&mut T>, Option<&mut T>) {
+        assert_ne!(a, b, "get2_mut called with the same key twice");
+
+        let (low, high) = (a.min(b), a.max(b));
+        let split = high.min(self.entries.len());
+        let (head, tail) = self.entries.split_at_mut(split);
+        let low = head.get_mut(low).and_then(Entry::value_mut);
+        let high = tail.first_mut().and_then(Entry::value_mut);
+        if a < b {
+            (low, high)
+        } else {
+            (high, low)
+        }
+    }
+
+    /// Returns `true` if a value is stored under `key`.
+    pub fn contains(&self, key: usize) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes all values. Keys are reused from 0 afterwards.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.len = 0;
+        self.next = 0;
+    }
+
+    /// Keeps only the values for which `f` returns `true`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &mut T) -> bool,
+    {
+        for key in 0..self.entries.len() {
+            let keep = match &mut self.entries[key] {
+                Entry::Occupied(value) => f(key, value),
+                Entry::Vacant(_) => true,
+            };
+            if !keep {
+                self.remove(key);
+            }
+        }
+    }
+
+    /// Iterates over occupied slots as `(key, &value)`, in key order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            entries: self.entries.iter().enumerate(),
+            len: self.len,
+        }
+    }
+
+    /// Iterates over occupied slots as `(key, &mut value)`, in key order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            entries: self.entries.iter_mut().enumerate(),
+            len: self.len,
+        }
+    }
+
+    /// Moves values from the end of the slab into holes so the occupied slots
+    /// become `0..len`, then releases the unused memory. `remap` is called
+    /// with each moved value and its old and new key, so references to it can
+    /// be updated.
+    pub fn compact<F>(&mut self, mut remap: F)
+    where
+        F: FnMut(&mut T, usize, usize),
+    {
+        let mut vacant = 0;
+        let mut last = self.entries.len();
+        while last > self.len {
+            last -= 1;
+            if let Entry::Vacant(_) = self.entries[last] {
+                continue;
+            }
+            while let Entry::Occupied(_) = self.entries[vacant] {
+                vacant += 1;
+            }
+            let entry = mem::replace(&mut self.entries[last], Entry::Vacant(0));
+            self.entries[vacant] = entry;
+            if let Entry::Occupied(value) = &mut self.entries[vacant] {
+                remap(value, last, vacant);
+            }
+        }
+
+        self.entries.truncate(self.len);
+        self.entries.shrink_to_fit();
+        self.next = self.len;
+    }
+
+    fn insert_at(&mut self, key: usize, value: T) {
+        if key == self.entries.len() {
+            self.entries.push(Entry::Occupied(value));
+            self.next = key + 1;
+        } else {
+            self.next = match mem::replace(&mut self.entries[key], Entry::Occupied(value)) {
+                Entry::Vacant(next) => next,
+                Entry::Occupied(_) => unreachable!("free list points to an occupied slot"),
+            };
+        }
+        self.len += 1;
+    }
+}
+
+impl<T> Entry<T> {
+    fn value_mut(&mut self) -> Option<&mut T> {
+        match self {
+            Entry::Occupied(value) => Some(value),
+            Entry::Vacant(_) => None,
+        }
+    }
+}
+
+impl<T> Index<usize> for Slab<T> {
+    type Output = T;
+
+    fn index(&self, key: usize) -> &T {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<T> IndexMut<usize> for Slab<T> {
+    fn index_mut(&mut self, key: usize) -> &mut T {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+/// A handle to a vacant slot, returned by [`Slab::vacant_entry`].
+pub struct VacantEntry<'a, T> {
+    key: usize,
+    slab: &'a mut Slab<T>,
+}
+
+impl<'a, T> VacantEntry<'a, T> {
+    /// Returns the key the value will be stored under.
+    pub fn key(&self) -> usize {
+        self.key
+    }
+
+    /// Stores a value in the slot and returns a reference to it.
+    pub fn insert(self, value: T) -> &'a mut T {
+        self.slab.insert_at(self.key, value);
+        match &mut self.slab.entries[self.key] {
+            Entry::Occupied(value) => value,
+            Entry::Vacant(_) => unreachable!(),
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    entries: std::iter::Enumerate<std::slice::Iter<'a, Entry<T>>>,
+    // Occupied slots left to yield.
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, entry) in self.entries.by_ref() {
+            if let Entry::Occupied(value) = entry {
+                self.len -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+pub struct IterMut<'a, T> {
+    entries: std::iter::Enumerate<std::slice::IterMut<'a, Entry<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (usize, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, entry) in self.entries.by_ref() {
+            if let Entry::Occupied(value) = entry {
+                self.len -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Slab<T> {
+    type Item = (usize, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Slab<T> {
+    type Item = (usize, &'a mut T);
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Slab;
+
+    #[test]
+    fn basic() {
+        let mut slab = Slab::new();
+        assert_eq!(slab.get(0), None);
+
+        let a = slab.insert("a");
+        let b = slab.insert("b");
+        let c = slab.insert("c");
+        assert_eq!((a, b, c), (0, 1, 2));
+        assert_eq!(slab.len(), 3);
+        assert_eq!(slab[b], "b");
+
+        assert_eq!(slab.remove(b), Some("b"));
+        assert_eq!(slab.remove(b), None);
+        assert!(!slab.contains(b));
+        assert_eq!(slab.get(c), Some(&"c"));
+        assert_eq!(slab.len(), 2);
+
+        // Holes are reused, most recently freed first
+        slab.remove(a);
+        assert_eq!(slab.insert("d"), a);
+        assert_eq!(slab.insert("e"), b);
+        assert_eq!(slab.insert("f"), 3);
+
+        *slab.get_mut(c).unwrap() = "C";
+        slab[a] = "D";
+        assert_eq!(
+            slab.iter().collect::<Vec<_>>(),
+            vec![(0, &"D"), (1, &"e"), (2, &"C"), (3, &"f")]
+        );
+    }
+
+    #[test]
+    fn vacant_entry() {
+        let mut slab = Slab::new();
+        slab.insert((0, "a"));
+
+        let entry = slab.vacant_entry();
+        let key = entry.key();
+        assert_eq!(entry.insert((key, "b")), &mut (1, "b"));
+        assert_eq!(slab[1], (1, "b"));
+    }
+
+    #[test]
+    fn iter() {
+        let mut slab = Slab::new();
+        for i in 0..6 {
+            slab.insert(i);
+        }
+        slab.remove(1);
+        slab.remove(4);
+
+        for (key, value) in &mut slab {
+            *value += key * 10;
+        }
+        let iter = slab.iter();
+        assert_eq!(iter.size_hint(), (4, Some(4)));
+        assert_eq!(
+            iter.collect::<Vec<_>>(),
+            vec![(0, &0), (2, &22), (3, &33), (5, &55)]
+        );
+    }
+
+    #[test]
+    fn get2_mut_retain() {
+        let mut slab = Slab::new();
+        for i in 0..6 {
+            slab.insert(i);
+        }
+        let (a, b) = slab.get2_mut(4, 1);
+        std::mem::swap(a.unwrap(), b.unwrap());
+        assert_eq!((slab[1], slab[4]), (4, 1));
+        assert_eq!(slab.get2_mut(2, 9), (Some(&mut 2), None));
+
+        slab.retain(|key, value| {
+            *value += 10;
+            key % 2 == 0
+        });
+        assert_eq!(
+            slab.iter().collect::<Vec<_>>(),
+            vec![(0, &10), (2, &12), (4, &11)]
+        );
+        assert_eq!(slab.insert(6), 5);
+    }
+
+    #[test]
+    fn compact() {
+        let mut slab = Slab::new();
+        for i in 0..6 {
+            slab.insert(i);
+        }
+        slab.remove(0);
+        slab.remove(2);
+        slab.remove(3);
+
+        let mut moves = Vec::new();
+        slab.compact(|value, from, to| moves.push((*value, from, to)));
+        assert_eq!(moves, vec![(5, 5, 0), (4, 4, 2)]);
+        assert_eq!(
+            slab.iter().collect::<Vec<_>>(),
+            vec![(0, &5), (1, &1), (2, &4)]
+        );
+
+        assert_eq!(slab.insert(6), 3);
+        assert_eq!(slab.len(), 4);
+    }
+}
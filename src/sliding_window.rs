@@ -0,0 +1,352 @@
+#![allow(unused)]
+//! Aggregates over the last `n` samples of a stream.
+//!
+//! Samples are kept in a [`circular_buffer::Buffer`](crate::circular_buffer::Buffer)
+//! which drops the oldest one on overflow. Sum, mean and variance are updated
+//! in *O*(1) per sample (Welford's algorithm adapted for a sliding window).
+//! Subtracting evicted samples lets rounding error pile up forever, so after
+//! every `size` evictions they're recomputed from the window, which keeps them
+//! amortized *O*(1). Min and max use monotonic deques, which gives amortized
+//! *O*(1) per sample.
+use crate::circular_buffer::Buffer;
+use std::collections::VecDeque;
+
+/// A numeric sample the window can aggregate.
+///
+/// Statistics are computed in `f64`, so integers wider than 53 bits are
+/// rounded to the nearest representable value.
+pub trait Sample: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_sample {
+    ($($t:ty),*) => {
+        $(
+            impl Sample for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_sample!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+pub struct SlidingWindow<T> {
+    samples: Buffer<T>,
+    size: usize,
+    // Sequence number of the next sample, used to expire min/max candidates.
+    seq: usize,
+    // Evictions since sum, mean and m2 were last recomputed.
+    evictions: usize,
+    sum: f64,
+    mean: f64,
+    // Sum of squared differences from the mean.
+    m2: f64,
+    // Candidates for the minimum, values increasing from front to back.
+    min: VecDeque<(usize, T)>,
+    // Candidates for the maximum, values decreasing from front to back.
+    max: VecDeque<(usize, T)>,
+}
+
+impl<T: Sample> SlidingWindow<T> {
+    /// Creates a window over the last `size` samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "window size must be non-zero");
+        Self {
+            // The ring keeps one slot free, so ask for one more.
+            samples: Buffer::with_capacity(size + 1),
+            size,
+            seq: 0,
+            evictions: 0,
+            sum: 0.0,
+            mean: 0.0,
+            m2: 0.0,
+            min: VecDeque::new(),
+            max: VecDeque::new(),
+        }
+    }
+
+    /// Adds a sample, evicting the oldest one if the window is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sample is NaN, which has no place in the min/max order,
+    /// or infinite, which would leave the sum and variance NaN for good.
+    pub fn push(&mut self, value: T) {
+        let x = value.to_f64();
+        assert!(x.is_finite(), "sample must be finite");
+        if self.samples.len() == self.size {
+            let y = self.samples.peek_front().expect("window is full").to_f64();
+            let old_mean = self.mean;
+            self.mean += (x - y) / self.size as f64;
+            self.m2 += (x - y) * (x - self.mean + y - old_mean);
+            self.sum += x - y;
+            self.evictions += 1;
+        } else {
+            let n = self.samples.len() + 1;
+            let delta = x - self.mean;
+            self.mean += delta / n as f64;
+            self.m2 += delta * (x - self.mean);
+            self.sum += x;
+        }
+        self.samples.push(value);
+        if self.evictions == self.size {
+            self.recompute();
+        }
+
+        while matches!(self.min.back(), Some((_, v)) if *v >= value) {
+            self.min.pop_back();
+        }
+        self.min.push_back((self.seq, value));
+        while matches!(self.max.back(), Some((_, v)) if *v <= value) {
+            self.max.pop_back();
+        }
+        self.max.push_back((self.seq, value));
+
+        self.seq += 1;
+        let oldest = self.seq.saturating_sub(self.size);
+        while matches!(self.min.front(), Some((i, _)) if *i < oldest) {
+            self.min.pop_front();
+        }
+        while matches!(self.max.front(), Some((i, _)) if *i < oldest) {
+            self.max.pop_front();
+        }
+    }
+
+    /// Returns the number of samples in the window.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns `true` once the window holds `size` samples.
+    pub fn is_full(&self) -> bool {
+        self.samples.len() == self.size
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.mean)
+        }
+    }
+
+    /// Returns the population variance of the samples in the window.
+    pub fn variance(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            // Rounding can push m2 slightly below zero for constant input.
+            Some(self.m2.max(0.0) / self.len() as f64)
+        }
+    }
+
+    /// Returns the sample (Bessel-corrected) variance, or `None` with fewer
+    /// than two samples.
+    pub fn sample_variance(&self) -> Option<f64> {
+        if self.len() < 2 {
+            None
+        } else {
+            Some(self.m2.max(0.0) / (self.len() - 1) as f64)
+        }
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.min.front().map(|(_, v)| *v)
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.max.front().map(|(_, v)| *v)
+    }
+
+    /// Returns the `p`-th percentile (`0.0..=100.0`) of the window, linearly
+    /// interpolating between the closest ranks.
+    ///
+    /// Sorts a copy of the window, so it's *O*(n log n) and meant for small
+    /// windows.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.is_empty() || !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+
+        let mut sorted: Vec<f64> = self.samples.iter().map(|v| v.to_f64()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let rank = p / 100.0 * (sorted.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        let fraction = rank - lower as f64;
+        Some(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction)
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.percentile(50.0)
+    }
+
+    /// Returns an iterator over the samples, oldest first.
+    pub fn iter(&self) -> crate::circular_buffer::Iter<'_, T> {
+        self.samples.iter()
+    }
+
+    /// Recomputes sum, mean and m2 from scratch with two passes over the
+    /// window.
+    fn recompute(&mut self) {
+        let n = self.samples.len() as f64;
+        let sum: f64 = self.samples.iter().map(|v| v.to_f64()).sum();
+        let mean = sum / n;
+        self.m2 = self
+            .samples
+            .iter()
+            .map(|v| (v.to_f64() - mean).powi(2))
+            .sum();
+        self.sum = sum;
+        self.mean = mean;
+        self.evictions = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SlidingWindow;
+
+    fn assert_close(a: Option<f64>, b: f64) {
+        let a = a.unwrap();
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn basic() {
+        let mut window = SlidingWindow::new(3);
+        assert_eq!(window.mean(), None);
+        assert_eq!(window.min(), None);
+        assert_eq!(window.max(), None);
+
+        window.push(4);
+        window.push(1);
+        assert!(!window.is_full());
+        assert_eq!(window.sum(), 5.0);
+        assert_close(window.mean(), 2.5);
+        assert_eq!(window.min(), Some(1));
+        assert_eq!(window.max(), Some(4));
+
+        window.push(7);
+        window.push(2); // evicts 4
+        assert!(window.is_full());
+        assert_eq!(window.iter().copied().collect::<Vec<_>>(), vec![1, 7, 2]);
+        assert_eq!(window.sum(), 10.0);
+        assert_eq!(window.min(), Some(1));
+        assert_eq!(window.max(), Some(7));
+
+        window.push(3); // evicts 1
+        assert_eq!(window.min(), Some(2));
+        window.push(5); // evicts 7
+        assert_eq!(window.max(), Some(5));
+    }
+
+    #[test]
+    fn matches_naive() {
+        let size = 5;
+        let mut window = SlidingWindow::new(size);
+        let samples: Vec<f64> = (0..200).map(|i| ((i * 37) % 23) as f64 - 11.5).collect();
+
+        for (i, &x) in samples.iter().enumerate() {
+            window.push(x);
+            let start = (i + 1).saturating_sub(size);
+            let expected = &samples[start..=i];
+            let n = expected.len() as f64;
+            let mean = expected.iter().sum::<f64>() / n;
+            let variance = expected.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+            let min = expected.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = expected.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+            assert_close(window.mean(), mean);
+            assert_close(window.variance(), variance);
+            assert_eq!(window.min(), Some(min));
+            assert_eq!(window.max(), Some(max));
+        }
+    }
+
+    #[test]
+    fn percentile() {
+        let mut window = SlidingWindow::new(4);
+        assert_eq!(window.median(), None);
+        for x in [10u32, 40, 20, 30, 50] {
+            window.push(x);
+        }
+        // Window holds 40, 20, 30, 50
+        assert_close(window.percentile(0.0), 20.0);
+        assert_close(window.median(), 35.0);
+        assert_close(window.percentile(100.0), 50.0);
+        assert_close(window.percentile(25.0), 27.5);
+        assert_eq!(window.percentile(101.0), None);
+    }
+
+    #[test]
+    fn no_drift() {
+        // Adding 1.0 to 1e17 is lost to rounding, so subtracting 1e17 again
+        // would leave the running sum off for good.
+        let mut window = SlidingWindow::new(3);
+        window.push(1e17);
+        for _ in 0..10 {
+            window.push(1.0);
+        }
+        assert_eq!(window.sum(), 3.0);
+        assert_eq!(window.mean(), Some(1.0));
+        assert_eq!(window.variance(), Some(0.0));
+    }
+
+    #[test]
+    fn wide_integers() {
+        let mut window = SlidingWindow::new(2);
+        window.push(u64::MAX);
+        window.push(1u64 << 40);
+        assert_eq!(window.max(), Some(u64::MAX));
+        assert_eq!(window.min(), Some(1 << 40));
+        assert_eq!(
+            window.mean(),
+            Some((u64::MAX as f64 + (1u64 << 40) as f64) / 2.0)
+        );
+        window.push(5);
+        assert_eq!(window.min(), Some(5));
+
+        let mut window = SlidingWindow::new(3);
+        for x in [-5i64, 3, 8] {
+            window.push(x);
+        }
+        assert_eq!(window.sum(), 6.0);
+        assert_eq!(window.min(), Some(-5));
+    }
+
+    #[test]
+    #[should_panic(expected = "finite")]
+    fn nan() {
+        let mut window = SlidingWindow::new(3);
+        window.push(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "finite")]
+    fn infinite() {
+        let mut window = SlidingWindow::new(3);
+        window.push(1.0);
+        window.push(f32::INFINITY);
+    }
+}
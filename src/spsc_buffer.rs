@@ -0,0 +1,358 @@
+#![allow(unused)]
+//! A lock-free single-producer single-consumer ring buffer.
+//!
+//! Uses the same head/tail layout as
+//! [`circular_buffer::Buffer`](crate::circular_buffer::Buffer): one slot is kept
+//! free so `head == tail` means empty. Only the producer writes `head` and only
+//! the consumer writes `tail`. Each side publishes its index with `Release`
+//! after touching a slot and loads the other side's index with `Acquire`, so a
+//! slot is never read before it's written nor overwritten before it's read.
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Pads and aligns a value to the size of a cache line, so the producer and
+/// consumer indices don't false-share.
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Shared storage of a [`Producer`]/[`Consumer`] pair.
+pub struct RingBuffer<T> {
+    buf: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+}
+
+// Slots are only accessed by the side that currently owns them, which is
+// guaranteed by the head/tail protocol.
+unsafe impl<T: Send> Sync for RingBuffer<T> {}
+
+/// The writing half of a [`RingBuffer`].
+pub struct Producer<T> {
+    inner: Arc<RingBuffer<T>>,
+}
+
+/// The reading half of a [`RingBuffer`].
+pub struct Consumer<T> {
+    inner: Arc<RingBuffer<T>>,
+    // Opts out of the automatic `Sync`, see below.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+// `peek` hands out `&T` through a shared reference, so sharing the consumer
+// shares the element between threads.
+unsafe impl<T: Send + Sync> Sync for Consumer<T> {}
+
+impl<T> RingBuffer<T> {
+    /// Creates a ring buffer holding up to `capacity` elements and returns its
+    /// producer and consumer halves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `usize::MAX`, since one slot is always kept
+    /// free.
+    pub fn with_capacity(capacity: usize) -> (Producer<T>, Consumer<T>) {
+        let slots = capacity.checked_add(1).expect("capacity overflow");
+        let buf = (0..slots)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+        let inner = Arc::new(Self {
+            buf,
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+        });
+        (
+            Producer {
+                inner: inner.clone(),
+            },
+            Consumer {
+                inner,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+
+    /// Returns the maximum number of elements the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.buf.len() - 1
+    }
+
+    /// Returns the number of elements currently in the buffer. The value may
+    /// be stale by the time it's used if the other side is active.
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        self.distance(tail, head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn next(&self, i: usize) -> usize {
+        (i + 1) % self.buf.len()
+    }
+
+    fn distance(&self, from: usize, to: usize) -> usize {
+        (to + self.buf.len() - from) % self.buf.len()
+    }
+
+    fn slot(&self, i: usize) -> *mut MaybeUninit<T> {
+        self.buf[i].get()
+    }
+}
+
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        let head = *self.head.0.get_mut();
+        let mut tail = *self.tail.0.get_mut();
+        while tail != head {
+            unsafe { self.buf[tail].get_mut().assume_init_drop() };
+            tail = self.next(tail);
+        }
+    }
+}
+
+impl<T> Producer<T> {
+    /// Appends an element, or gives it back if the buffer is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let head = self.inner.head.load(Ordering::Relaxed);
+        let next = self.inner.next(head);
+        if next == self.inner.tail.load(Ordering::Acquire) {
+            return Err(value);
+        }
+
+        unsafe { (*self.inner.slot(head)).write(value) };
+        self.inner.head.store(next, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pushes elements from `iter` until the buffer is full and returns how
+    /// many were pushed. Items beyond the free space are not pulled from the
+    /// iterator, so pass `iter.by_ref()` to keep the rest. The new elements
+    /// become visible to the consumer at once.
+    pub fn push_iter<I>(&mut self, iter: I) -> usize
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut head = self.inner.head.load(Ordering::Relaxed);
+        let mut pushed = 0;
+        for value in iter.into_iter().take(self.free()) {
+            unsafe { (*self.inner.slot(head)).write(value) };
+            head = self.inner.next(head);
+            pushed += 1;
+        }
+        self.inner.head.store(head, Ordering::Release);
+        pushed
+    }
+
+    /// Copies as many elements from `values` as fit and returns how many were
+    /// pushed.
+    pub fn push_slice(&mut self, values: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        self.push_iter(values.iter().copied())
+    }
+
+    /// Returns the number of elements that can be pushed without blocking.
+    pub fn free(&self) -> usize {
+        let head = self.inner.head.load(Ordering::Relaxed);
+        let tail = self.inner.tail.load(Ordering::Acquire);
+        self.inner.capacity() - self.inner.distance(tail, head)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.free() == 0
+    }
+
+    pub fn buffer(&self) -> &RingBuffer<T> {
+        &self.inner
+    }
+}
+
+impl<T> Consumer<T> {
+    /// Removes the oldest element and returns it, or `None` if the buffer is
+    /// empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let tail = self.inner.tail.load(Ordering::Relaxed);
+        if tail == self.inner.head.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let value = unsafe { (*self.inner.slot(tail)).assume_init_read() };
+        self.inner
+            .tail
+            .store(self.inner.next(tail), Ordering::Release);
+        Some(value)
+    }
+
+    /// Provides a reference to the oldest element, or `None` if the buffer is
+    /// empty.
+    pub fn peek(&self) -> Option<&T> {
+        let tail = self.inner.tail.load(Ordering::Relaxed);
+        if tail == self.inner.head.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(unsafe { (*self.inner.slot(tail)).assume_init_ref() })
+    }
+
+    /// Moves as many elements as are available into `out` and returns how
+    /// many were popped. The freed slots become visible to the producer at
+    /// once.
+    pub fn pop_slice(&mut self, out: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let mut tail = self.inner.tail.load(Ordering::Relaxed);
+        let head = self.inner.head.load(Ordering::Acquire);
+        let n = std::cmp::min(out.len(), self.inner.distance(tail, head));
+        for value in out.iter_mut().take(n) {
+            *value = unsafe { (*self.inner.slot(tail)).assume_init_read() };
+            tail = self.inner.next(tail);
+        }
+        self.inner.tail.store(tail, Ordering::Release);
+        n
+    }
+
+    pub fn buffer(&self) -> &RingBuffer<T> {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Consumer, Producer, RingBuffer};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn auto_traits() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+        is_send::<Producer<Cell<u32>>>();
+        is_send::<Consumer<Cell<u32>>>();
+        is_sync::<Producer<Cell<u32>>>();
+        is_sync::<Consumer<u32>>();
+
+        // Only compiles if exactly one impl applies, i.e. if the consumer of
+        // a non-`Sync` element isn't `Sync`.
+        trait AmbiguousIfSync<A> {
+            fn check() {}
+        }
+        impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+        struct IsSync;
+        impl<T: ?Sized + Sync> AmbiguousIfSync<IsSync> for T {}
+        <Consumer<Cell<u32>> as AmbiguousIfSync<_>>::check();
+    }
+
+    #[test]
+    fn basic() {
+        let (mut producer, mut consumer) = RingBuffer::with_capacity(2);
+        assert_eq!(producer.buffer().capacity(), 2);
+        assert_eq!(consumer.pop(), None);
+
+        assert_eq!(producer.push(1), Ok(()));
+        assert_eq!(producer.push(2), Ok(()));
+        assert_eq!(producer.push(3), Err(3));
+        assert!(producer.is_full());
+        assert_eq!(consumer.buffer().len(), 2);
+
+        assert_eq!(consumer.peek(), Some(&1));
+        assert_eq!(consumer.pop(), Some(1));
+        assert_eq!(producer.push(3), Ok(()));
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), Some(3));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn capacity_overflow() {
+        RingBuffer::<u8>::with_capacity(usize::MAX);
+    }
+
+    #[test]
+    fn batch() {
+        let (mut producer, mut consumer) = RingBuffer::with_capacity(4);
+        let mut iter = 0..6;
+        assert_eq!(producer.push_iter(iter.by_ref()), 4);
+        assert_eq!(iter.next(), Some(4));
+
+        let mut out = [0; 3];
+        assert_eq!(consumer.pop_slice(&mut out), 3);
+        assert_eq!(out, [0, 1, 2]);
+
+        assert_eq!(producer.push_slice(&[7, 8, 9, 10]), 3);
+        let mut out = [0; 8];
+        assert_eq!(consumer.pop_slice(&mut out), 4);
+        assert_eq!(&out[..4], &[3, 7, 8, 9]);
+    }
+
+    #[test]
+    fn drop_remaining() {
+        let value = Rc::new(());
+        let (mut producer, mut consumer) = RingBuffer::with_capacity(3);
+        producer.push(value.clone()).unwrap();
+        producer.push(value.clone()).unwrap();
+        producer.push(value.clone()).unwrap();
+        consumer.pop();
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        drop(producer);
+        drop(consumer);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn stress() {
+        const COUNT: usize = 50_000;
+        let (mut producer, mut consumer) = RingBuffer::with_capacity(64);
+
+        let writer = thread::spawn(move || {
+            let mut next = 0;
+            while next < COUNT {
+                if next % 3 == 0 {
+                    let end = std::cmp::min(next + 16, COUNT);
+                    next += producer.push_iter(next..end);
+                } else if producer.push(next).is_ok() {
+                    next += 1;
+                } else {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        let mut out = [0; 8];
+        while expected < COUNT {
+            let n = consumer.pop_slice(&mut out);
+            for value in &out[..n] {
+                assert_eq!(*value, expected);
+                expected += 1;
+            }
+            match consumer.pop() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        writer.join().unwrap();
+        assert_eq!(consumer.pop(), None);
+    }
+}
@@ -0,0 +1,999 @@
+#![allow(unused)]
+//! Doubly linked list over raw `NonNull` pointers, with the same API as
+//! `std::collections::LinkedList`, including cursors.
+//!
+//! Unlike the `Rc<RefCell<_>>` list in `doubly_linked_deque`, there are no
+//! reference counts or borrow flags: the list owns every node and hands out
+//! plain references tied to its own borrow. `PhantomData<T>` marks that
+//! ownership, which keeps the list covariant in `T` and tells drop check that
+//! dropping the list drops elements.
+//!
+//! The tests are meant to be run under Miri
+//! (`cargo +nightly miri test unsafe_doubly_linked_list`) to check every
+//! pointer path for undefined behaviour.
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem,
+    ptr::NonNull,
+};
+
+/// A doubly-linked list owning its nodes through raw pointers.
+pub struct LinkedList<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _owns: PhantomData<T>,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    next: Link<T>,
+    prev: Link<T>,
+    element: T,
+}
+
+impl<T> LinkedList<T> {
+    /// Creates an empty `LinkedList`.
+    pub const fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all elements from the list.
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    /// Adds an element first in the list.
+    pub fn push_front(&mut self, elt: T) {
+        let head = self.head;
+        self.splice_between(None, head, Self::single(elt));
+    }
+
+    /// Appends an element to the back of a list.
+    pub fn push_back(&mut self, elt: T) {
+        let tail = self.tail;
+        self.splice_between(tail, None, Self::single(elt));
+    }
+
+    /// Removes the first element and returns it, or `None` if the list is
+    /// empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        // The head belongs to this list.
+        self.head.map(|node| unsafe { self.unlink(node) })
+    }
+
+    /// Removes the last element from a list and returns it, or `None` if
+    /// it is empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        // The tail belongs to this list.
+        self.tail.map(|node| unsafe { self.unlink(node) })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.tail
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    /// Returns `true` if the list contains an element equal to `x`.
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|e| e == x)
+    }
+
+    /// Moves all elements from `other` to the end of the list, leaving
+    /// `other` empty. This runs in *O*(1).
+    pub fn append(&mut self, other: &mut Self) {
+        let tail = self.tail;
+        self.splice_between(tail, None, mem::take(other));
+    }
+
+    /// Splits the list into two at the given index. Returns everything after
+    /// the given index, including the index. This runs in
+    /// *O*(min(at, n - at)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "cannot split off at a nonexistent index");
+        if at == 0 {
+            return mem::take(self);
+        }
+        let last = self.node_at(at - 1);
+        self.split_after_node(Some(last), at)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Provides a cursor at the front element, or at the "ghost" position if
+    /// the list is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    /// Provides a cursor at the back element, or at the "ghost" position if
+    /// the list is empty.
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.tail,
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+
+    /// Provides a cursor with editing operations at the front element.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    /// Provides a cursor with editing operations at the back element.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail,
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+
+    /// Returns a list holding only `elt`.
+    fn single(elt: T) -> Self {
+        let node = Box::new(Node {
+            next: None,
+            prev: None,
+            element: elt,
+        });
+        let node = NonNull::from(Box::leak(node));
+        Self {
+            head: Some(node),
+            tail: Some(node),
+            len: 1,
+            _owns: PhantomData,
+        }
+    }
+
+    /// Returns the node at index `i`, walking from the nearer end.
+    fn node_at(&self, i: usize) -> NonNull<Node<T>> {
+        let (mut node, steps, forward) = if i <= self.len / 2 {
+            (self.head, i, true)
+        } else {
+            (self.tail, self.len - 1 - i, false)
+        };
+        for _ in 0..steps {
+            node = node.and_then(|node| unsafe {
+                if forward {
+                    (*node.as_ptr()).next
+                } else {
+                    (*node.as_ptr()).prev
+                }
+            });
+        }
+        node.expect("index out of bounds")
+    }
+
+    /// Links all of `other` in between two adjacent positions: `prev` and
+    /// `next` are neighbours in the list, or `None` at either end.
+    fn splice_between(&mut self, prev: Link<T>, next: Link<T>, mut other: Self) {
+        let (Some(head), Some(tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        self.len += mem::take(&mut other.len);
+
+        unsafe {
+            (*head.as_ptr()).prev = prev;
+            (*tail.as_ptr()).next = next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(head),
+                None => self.head = Some(head),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(tail),
+                None => self.tail = Some(tail),
+            }
+        }
+    }
+
+    /// Cuts the list after `last`, which becomes the new tail, and returns
+    /// the rest. `len_before` is the number of elements up to and including
+    /// `last`. If `last` is `None`, the whole list is returned.
+    fn split_after_node(&mut self, last: Link<T>, len_before: usize) -> Self {
+        let Some(last) = last else {
+            return mem::take(self);
+        };
+        unsafe {
+            let Some(first) = (*last.as_ptr()).next.take() else {
+                return Self::new();
+            };
+            (*first.as_ptr()).prev = None;
+
+            let tail = self.tail.replace(last);
+            let rest = Self {
+                head: Some(first),
+                tail,
+                len: self.len - len_before,
+                _owns: PhantomData,
+            };
+            self.len = len_before;
+            rest
+        }
+    }
+
+    /// Unlinks `node`, frees it and returns its element.
+    ///
+    /// # Safety
+    ///
+    /// `node` must belong to this list.
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        let node = Box::from_raw(node.as_ptr());
+        match node.prev {
+            Some(prev) => (*prev.as_ptr()).next = node.next,
+            None => self.head = node.next,
+        }
+        match node.next {
+            Some(next) => (*next.as_ptr()).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        self.len -= 1;
+        node.element
+    }
+}
+
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for LinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.push_back(x);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for LinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for LinkedList<T> {}
+
+impl<T: PartialOrd> PartialOrd for LinkedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for LinkedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash> Hash for LinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for x in self {
+            x.hash(state);
+        }
+    }
+}
+
+// The list owns its elements, so it can move between or be shared across
+// threads whenever they can.
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+unsafe impl<T: Sync> Send for Cursor<'_, T> {}
+unsafe impl<T: Sync> Sync for Cursor<'_, T> {}
+
+unsafe impl<T: Send> Send for CursorMut<'_, T> {}
+unsafe impl<T: Sync> Sync for CursorMut<'_, T> {}
+
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+pub struct IntoIter<T>(LinkedList<T>);
+
+// `len` rather than the links decides when to stop, so the two ends never
+// hand out the same node.
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            self.len -= 1;
+            self.head = (*node.as_ptr()).next;
+            &(*node.as_ptr()).element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.len -= 1;
+            self.tail = (*node.as_ptr()).prev;
+            &(*node.as_ptr()).element
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self { ..*self }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            self.len -= 1;
+            self.head = (*node.as_ptr()).next;
+            &mut (*node.as_ptr()).element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.len -= 1;
+            self.tail = (*node.as_ptr()).prev;
+            &mut (*node.as_ptr()).element
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// A cursor over a `LinkedList`.
+///
+/// A cursor points at an element, or at a "ghost" non-element between the
+/// back and the front of the list. Moving past either end lands on the ghost,
+/// and moving on from the ghost wraps around to the other end.
+pub struct Cursor<'a, T> {
+    current: Link<T>,
+    index: usize,
+    list: &'a LinkedList<T>,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Returns the index of the current element, or `None` at the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    /// Moves to the next element.
+    pub fn move_next(&mut self) {
+        match self.current {
+            Some(node) => {
+                self.current = unsafe { (*node.as_ptr()).next };
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+        }
+    }
+
+    /// Moves to the previous element.
+    pub fn move_prev(&mut self) {
+        match self.current {
+            Some(node) => {
+                self.current = unsafe { (*node.as_ptr()).prev };
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            }
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Returns the current element, or `None` at the ghost.
+    pub fn current(&self) -> Option<&'a T> {
+        self.current
+            .map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    /// Returns the next element without moving.
+    pub fn peek_next(&self) -> Option<&'a T> {
+        let next = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => self.list.head,
+        };
+        next.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    /// Returns the previous element without moving.
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        let prev = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        prev.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Self { ..*self }
+    }
+}
+
+/// A cursor over a `LinkedList` that can also edit the list.
+pub struct CursorMut<'a, T> {
+    current: Link<T>,
+    index: usize,
+    list: &'a mut LinkedList<T>,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    /// Returns the index of the current element, or `None` at the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    /// Moves to the next element.
+    pub fn move_next(&mut self) {
+        match self.current {
+            Some(node) => {
+                self.current = unsafe { (*node.as_ptr()).next };
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+        }
+    }
+
+    /// Moves to the previous element.
+    pub fn move_prev(&mut self) {
+        match self.current {
+            Some(node) => {
+                self.current = unsafe { (*node.as_ptr()).prev };
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            }
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Returns the current element, or `None` at the ghost.
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    /// Returns the next element without moving.
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => self.list.head,
+        };
+        next.map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    /// Returns the previous element without moving.
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        prev.map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    /// Returns a read-only cursor at the same position.
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.current,
+            index: self.index,
+            list: self.list,
+        }
+    }
+
+    /// Inserts an element before the current one. At the ghost, the element
+    /// becomes the new back.
+    pub fn insert_before(&mut self, elt: T) {
+        self.splice_before(LinkedList::single(elt));
+    }
+
+    /// Inserts an element after the current one. At the ghost, the element
+    /// becomes the new front.
+    pub fn insert_after(&mut self, elt: T) {
+        self.splice_after(LinkedList::single(elt));
+    }
+
+    /// Removes the current element and moves to the next one. Returns `None`
+    /// and does nothing at the ghost.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current?;
+        unsafe {
+            self.current = (*node.as_ptr()).next;
+            Some(self.list.unlink(node))
+        }
+    }
+
+    /// Moves all elements of `other` in after the current one. At the ghost,
+    /// they're inserted at the front.
+    pub fn splice_after(&mut self, other: LinkedList<T>) {
+        let next = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => {
+                self.index += other.len;
+                self.list.head
+            }
+        };
+        self.list.splice_between(self.current, next, other);
+    }
+
+    /// Moves all elements of `other` in before the current one. At the ghost,
+    /// they're inserted at the back.
+    pub fn splice_before(&mut self, other: LinkedList<T>) {
+        let prev = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        self.index += other.len;
+        self.list.splice_between(prev, self.current, other);
+    }
+
+    /// Splits the list after the current element and returns everything
+    /// after it. At the ghost, the whole list is returned.
+    pub fn split_after(&mut self) -> LinkedList<T> {
+        let len_before = match self.current {
+            Some(_) => self.index + 1,
+            None => 0,
+        };
+        let rest = self.list.split_after_node(self.current, len_before);
+        if self.current.is_none() {
+            self.index = 0;
+        }
+        rest
+    }
+
+    /// Splits the list before the current element and returns everything
+    /// before it. At the ghost, the whole list is returned.
+    pub fn split_before(&mut self) -> LinkedList<T> {
+        let prev = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        let mut front = self.list.split_after_node(prev, self.index);
+        mem::swap(self.list, &mut front);
+        self.index = 0;
+        front
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn list_from(values: &[i32]) -> LinkedList<i32> {
+        values.iter().copied().collect()
+    }
+
+    fn to_vec(list: &LinkedList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    // Only needs to compile.
+    #[allow(dead_code)]
+    fn properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<LinkedList<i32>>();
+        is_sync::<LinkedList<i32>>();
+        is_send::<IntoIter<i32>>();
+        is_sync::<IntoIter<i32>>();
+        is_send::<Iter<'_, i32>>();
+        is_sync::<Iter<'_, i32>>();
+        is_send::<IterMut<'_, i32>>();
+        is_sync::<IterMut<'_, i32>>();
+        is_send::<Cursor<'_, i32>>();
+        is_sync::<Cursor<'_, i32>>();
+        is_send::<CursorMut<'_, i32>>();
+        is_sync::<CursorMut<'_, i32>>();
+
+        fn list_covariant<'a, T>(x: LinkedList<&'static T>) -> LinkedList<&'a T> {
+            x
+        }
+        fn iter_covariant<'i, 'a, T>(x: Iter<'i, &'static T>) -> Iter<'i, &'a T> {
+            x
+        }
+        fn into_iter_covariant<'a, T>(x: IntoIter<&'static T>) -> IntoIter<&'a T> {
+            x
+        }
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = LinkedList::new();
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_front(10);
+        list.push_front(20);
+        list.push_back(30);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.front(), Some(&20));
+        assert_eq!(list.back(), Some(&30));
+
+        *list.front_mut().unwrap() += 1;
+        *list.back_mut().unwrap() += 1;
+        assert_eq!(list.pop_front(), Some(21));
+        assert_eq!(list.pop_back(), Some(31));
+        assert_eq!(list.pop_back(), Some(10));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+
+        list.push_back(7);
+        assert_eq!(list.front(), list.back());
+        list.clear();
+        assert!(list.front().is_none());
+    }
+
+    #[test]
+    fn iter() {
+        let mut list = list_from(&[1, 2, 3, 4, 5]);
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        for x in &mut list {
+            *x *= 10;
+        }
+        let mut iter = list.iter_mut();
+        *iter.next_back().unwrap() += 1;
+        *iter.next().unwrap() += 2;
+        assert_eq!(to_vec(&list), vec![12, 20, 30, 40, 51]);
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next_back(), Some(51));
+        assert_eq!(iter.next(), Some(12));
+        assert_eq!(iter.len(), 3);
+        // Dropping the iterator drops the rest
+    }
+
+    #[test]
+    fn traits() {
+        let list = list_from(&[1, 2, 3]);
+        let copy = list.clone();
+        assert_eq!(list, copy);
+        assert!(list < list_from(&[1, 2, 4]));
+        assert!(list > list_from(&[1, 2]));
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        assert!(list.contains(&2));
+        assert!(!list.contains(&4));
+
+        let hash = |list: &LinkedList<i32>| {
+            let mut hasher = DefaultHasher::new();
+            list.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&list), hash(&copy));
+    }
+
+    #[test]
+    fn append_split_off() {
+        let mut list = list_from(&[0, 1, 2]);
+        let mut other = list_from(&[3, 4, 5]);
+        list.append(&mut other);
+        assert!(other.is_empty());
+        other.append(&mut list);
+        assert!(list.is_empty());
+        assert_eq!(other.len(), 6);
+
+        let back = other.split_off(4);
+        assert_eq!(to_vec(&back), vec![4, 5]);
+        let back = other.split_off(1);
+        assert_eq!(to_vec(&back), vec![1, 2, 3]);
+        assert!(other.split_off(1).is_empty());
+        assert_eq!(to_vec(&other.split_off(0)), vec![0]);
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    fn cursor() {
+        let list = list_from(&[1, 2, 3]);
+        let mut cursor = list.cursor_back();
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.peek_next(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&1));
+        assert_eq!(cursor.peek_prev(), Some(&3));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&2));
+
+        // References outlive the cursor
+        let current = {
+            let mut cursor = list.cursor_front();
+            cursor.move_prev();
+            cursor.move_prev();
+            cursor.current()
+        };
+        assert_eq!(current, Some(&3));
+    }
+
+    #[test]
+    fn cursor_mut() {
+        let mut list = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        *cursor.current().unwrap() *= 10;
+        *cursor.peek_prev().unwrap() *= 10;
+        cursor.insert_before(7);
+        cursor.insert_after(8);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.remove_current(), Some(20));
+        assert_eq!(cursor.current(), Some(&mut 8));
+        assert_eq!(cursor.as_cursor().index(), Some(2));
+
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        cursor.insert_after(0);
+        cursor.insert_before(9);
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(8));
+        assert_eq!(cursor.remove_current(), Some(9));
+        assert_eq!(cursor.remove_current(), None);
+        assert_eq!(to_vec(&list), vec![0, 10, 7, 8, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn cursor_split_splice() {
+        let mut list = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let front = cursor.split_before();
+        let back = cursor.split_after();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(to_vec(&front), vec![1, 2]);
+        assert_eq!(to_vec(&back), vec![4, 5, 6]);
+
+        cursor.splice_after(back);
+        cursor.splice_before(front);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 4, 5, 6]);
+
+        // Splicing at the ghost wraps around
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        cursor.splice_after(list_from(&[-1, 0]));
+        cursor.splice_before(list_from(&[7]));
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(8));
+        assert_eq!(cursor.split_after().len(), 0);
+        cursor.move_next();
+        assert_eq!(cursor.split_before().len(), 9);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn miri_food() {
+        let mut list = LinkedList::new();
+        for i in 0..6 {
+            list.push_back(Box::new(i));
+            list.push_front(Box::new(-i));
+        }
+
+        let mut cursor = list.cursor_front_mut();
+        while let Some(x) = cursor.current() {
+            if **x % 2 == 0 {
+                cursor.remove_current();
+            } else {
+                **x *= 10;
+                cursor.move_next();
+            }
+        }
+
+        let mut rest = list.split_off(2);
+        rest.push_front(Box::new(100));
+        let mut iter = rest.iter_mut();
+        while let (Some(front), Some(back)) = (iter.next(), iter.next_back()) {
+            std::mem::swap(front, back);
+        }
+        list.append(&mut rest);
+        let values: Vec<i32> = list.iter().map(|x| **x).collect();
+        assert_eq!(values, vec![-50, -30, 50, 30, 10, -10, 100]);
+
+        // Drop a half-consumed iterator and a list of boxes
+        let mut iter = list.clone().into_iter();
+        iter.next();
+        iter.next_back();
+    }
+}